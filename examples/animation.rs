@@ -0,0 +1,100 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!();
+
+fn main() {
+    let mut game = Game::new();
+
+    // An animation loaded from a file in assets/sprite
+    if let Some(animation) = Animation::from_file("rolling/ball_blue.animation") {
+        game.add_animation("blue_blink", animation);
+    }
+    // Animations built in code
+    game.add_animation(
+        "car_colors",
+        Animation::from_files(
+            [
+                SpritePreset::RacingCarBlack,
+                SpritePreset::RacingCarBlue,
+                SpritePreset::RacingCarGreen,
+                SpritePreset::RacingCarRed,
+                SpritePreset::RacingCarYellow,
+            ],
+            0.2,
+            AnimationMode::PingPong,
+        ),
+    );
+    game.add_animation(
+        "red_flash",
+        Animation::from_files(
+            [
+                SpritePreset::RollingBallRedAlt,
+                SpritePreset::RollingBallRed,
+                SpritePreset::RollingBallRedAlt,
+                SpritePreset::RollingBallRed,
+            ],
+            0.1,
+            AnimationMode::Once,
+        ),
+    );
+
+    let ball = game.add_sprite("blue_ball", SpritePreset::RollingBallBlue);
+    ball.translation = Vec2::new(-300.0, 0.0);
+    ball.play_animation("blue_blink");
+
+    let car = game.add_sprite("car", SpritePreset::RacingCarBlack);
+    car.play_animation("car_colors");
+
+    let ball = game.add_sprite("red_ball", SpritePreset::RollingBallRed);
+    ball.translation = Vec2::new(300.0, 0.0);
+
+    let msg = game.add_text(
+        "instructions",
+        "Space pauses and resumes the car. Click to flash the red ball.",
+    );
+    msg.font_size = 24.0;
+    msg.translation.y = 250.0;
+
+    let status = game.add_text("status", "");
+    status.font_size = 20.0;
+    status.translation.y = -250.0;
+
+    game.add_logic(logic);
+    game.run(());
+}
+
+fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
+    // Pause or resume the car's animation
+    if engine_state.keyboard_state.just_pressed(KeyCode::Space) {
+        let car = engine_state.sprites.get_mut("car").unwrap();
+        if car
+            .animation
+            .as_ref()
+            .map_or(false, |animation| animation.paused)
+        {
+            car.resume_animation();
+        } else {
+            car.pause_animation();
+        }
+    }
+
+    // Flash the red ball once per click. A finished animation starts over when played again.
+    let red_ball = engine_state.sprites.get_mut("red_ball").unwrap();
+    for mouse_button_input in engine_state.mouse_button_events.iter() {
+        if mouse_button_input.state == ElementState::Pressed {
+            red_ball.play_animation("red_flash");
+        }
+    }
+    // Once the flash is over, go back to the ball's own image
+    if red_ball.animation_finished() {
+        red_ball.stop_animation();
+    }
+
+    // Show which frame the car is on
+    let car = engine_state.sprites.get("car").unwrap();
+    if let Some(animation) = car.animation.as_ref() {
+        let status = engine_state.texts.get_mut("status").unwrap();
+        status.value = format!("Car frame: {}", animation.frame);
+    }
+    true
+}
@@ -0,0 +1,61 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!();
+
+const GRAVITY: f32 = -400.0;
+
+fn main() {
+    let mut game = Game::new();
+    let msg = game.add_text(
+        "instructions",
+        "Balls bounce off of the barriers all on their own. Toggle collider visibility with C.",
+    );
+    msg.font_size = 20.0;
+    msg.translation.y = 340.0;
+
+    // Static barriers around the edges of the screen, stretched into long walls. Nothing can push
+    // these around.
+    let walls = [
+        ("top_wall", Vec2::new(0.0, 300.0), RIGHT, 5.0),
+        ("bottom_wall", Vec2::new(0.0, -300.0), RIGHT, 5.0),
+        ("left_wall", Vec2::new(-550.0, 0.0), UP, 3.0),
+        ("right_wall", Vec2::new(550.0, 0.0), UP, 3.0),
+    ];
+    for (label, translation, rotation, length) in walls {
+        let wall = game.add_sprite(label, SpritePreset::RacingBarrierRed);
+        wall.translation = translation;
+        wall.rotation = rotation;
+        wall.stretch = Vec2::new(length, 1.0);
+        wall.collision = true;
+        wall.body_type = BodyType::Static;
+    }
+
+    // Dynamic balls that fall and bounce off of the barriers
+    let ball_presets = [
+        SpritePreset::RollingBallBlue,
+        SpritePreset::RollingBallRed,
+        SpritePreset::RollingBallBlueAlt,
+        SpritePreset::RollingBallRedAlt,
+    ];
+    for (i, preset) in ball_presets.iter().cycle().take(8).enumerate() {
+        let ball = game.add_sprite(format!("ball{}", i), *preset);
+        ball.translation = Vec2::new(-350.0 + 100.0 * i as f32, 150.0);
+        ball.velocity = Vec2::new(50.0 * i as f32 - 200.0, 0.0);
+        ball.acceleration = Vec2::new(0.0, GRAVITY);
+        ball.collision = true;
+        ball.body_type = BodyType::Dynamic;
+        // Each ball is a little bouncier than the last
+        ball.restitution = 0.5 + 0.07 * i as f32;
+    }
+
+    game.add_logic(logic);
+    game.run(());
+}
+
+fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
+    // Pressing C toggles sprite collider debug lines
+    if engine_state.keyboard_state.just_pressed(KeyCode::C) {
+        engine_state.debug_sprite_colliders = !engine_state.debug_sprite_colliders;
+    }
+    true
+}
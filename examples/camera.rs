@@ -0,0 +1,120 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!();
+
+const CAR_SPEED: f32 = 400.0;
+const TURN_SPEED: f32 = 3.0;
+// The world stretches this far from the origin in each direction
+const WORLD_WIDTH: f32 = 1500.0;
+const WORLD_HEIGHT: f32 = 1000.0;
+
+fn main() {
+    let mut game = Game::new();
+
+    let car = game.add_sprite("car", SpritePreset::RacingCarRed);
+    car.layer = 10.0;
+
+    // Scatter barrels around the world so there is something to look at while driving
+    let presets = [
+        SpritePreset::RacingBarrelBlue,
+        SpritePreset::RacingBarrelRed,
+        SpritePreset::RacingConeStraight,
+    ];
+    let mut count = 0;
+    for x in (-WORLD_WIDTH as i32 + 100..WORLD_WIDTH as i32).step_by(300) {
+        for y in (-WORLD_HEIGHT as i32 + 100..WORLD_HEIGHT as i32).step_by(300) {
+            if x == 0 && y == 0 {
+                continue;
+            }
+            let barrel = game.add_sprite(format!("barrel{}", count), presets[count % 3]);
+            barrel.translation = Vec2::new(x as f32, y as f32);
+            count += 1;
+        }
+    }
+
+    // The camera follows the car, but lets it move around a bit in the middle of the screen first.
+    // It never shows anything past the edges of the world.
+    game.camera.follow = Some("car".into());
+    game.camera.follow_deadzone = Vec2::new(300.0, 200.0);
+    game.camera.follow_lerp = 0.08;
+    game.camera.bounds = Some((-world_max(), world_max()));
+
+    let msg = game.add_text(
+        "instructions",
+        "Drive with the arrow keys. Zoom with the mousewheel. Rotate the camera with Q and E.\nPress Space to shake the camera. Click to drop a cone where the mouse is.",
+    );
+    msg.font_size = 20.0;
+    msg.screen_anchor = Some(ScreenAnchor::Top);
+    msg.translation.y = -40.0;
+
+    // An arrow in the corner of the screen that always points back to where the car started
+    let arrow = game.add_shape(
+        "home_arrow",
+        SpriteShape::regular_polygon(3, 25.0, Color::YELLOW).with_outline(Color::BLACK, 3.0),
+    );
+    arrow.screen_anchor = Some(ScreenAnchor::BottomRight);
+    arrow.translation = Vec2::new(-60.0, 60.0);
+    arrow.layer = 950.0;
+
+    game.add_logic(logic);
+    game.run(());
+}
+
+fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
+    // Drive the car
+    let keyboard_state = &engine_state.keyboard_state;
+    let mut turn = 0.0;
+    let mut throttle = 0.0;
+    if keyboard_state.pressed(KeyCode::Left) {
+        turn += 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Right) {
+        turn -= 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Up) {
+        throttle += 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Down) {
+        throttle -= 0.5;
+    }
+    let car = engine_state.sprites.get_mut("car").unwrap();
+    car.rotation += turn * TURN_SPEED * engine_state.delta_f32;
+    car.velocity = Vec2::new(car.rotation.cos(), car.rotation.sin()) * throttle * CAR_SPEED;
+    car.translation = car.translation.clamp(-world_max(), world_max());
+    let to_start = -car.translation;
+
+    // Zoom and rotate the camera
+    let camera = &mut engine_state.camera;
+    let wheel_direction = engine_state.mouse_state.wheel().y;
+    camera.zoom = (camera.zoom * (1.0 + wheel_direction * 0.1)).clamp(0.25, 4.0);
+    if keyboard_state.pressed(KeyCode::Q) {
+        camera.rotation += engine_state.delta_f32;
+    }
+    if keyboard_state.pressed(KeyCode::E) {
+        camera.rotation -= engine_state.delta_f32;
+    }
+    if keyboard_state.just_pressed(KeyCode::Space) {
+        camera.shake(15.0, 0.6);
+    }
+
+    // Sprites on the UI layer don't rotate with the camera, so take the camera's rotation into
+    // account to point the arrow in the right direction
+    let arrow = engine_state.sprites.get_mut("home_arrow").unwrap();
+    arrow.rotation = to_start.y.atan2(to_start.x) - engine_state.camera.rotation;
+
+    // The mouse location is already in world space, no matter where the camera is looking
+    if engine_state.mouse_state.just_pressed(MouseButton::Left) {
+        if let Some(location) = engine_state.mouse_state.location() {
+            let label = format!("cone{}", engine_state.time_since_startup_f64);
+            let cone = engine_state.add_sprite(label, SpritePreset::RacingConeStraight);
+            cone.translation = location;
+            cone.layer = 5.0;
+        }
+    }
+    true
+}
+
+/// The top-right corner of the world
+fn world_max() -> Vec2 {
+    Vec2::new(WORLD_WIDTH, WORLD_HEIGHT)
+}
@@ -57,7 +57,7 @@ fn main() {
     \n\
     1-9: Set Zoom level (sprite scale) to this amount.\n\
     Del/Backspace: Delete existing collider.*\n\
-    Mouse Click: Add a collider point. Add points in a CLOCKWISE direction. Must be a CONVEX polygon to work correctly!\n\
+    Mouse Click: Add a collider point. Add points in a CLOCKWISE direction. Edges must not cross each other!\n\
     - Hold SHIFT while clicking the mouse to change the LAST point added.\n\
     c: Generate a circle collider at the current radius (radius starts at 16.0)*\n\
     +: Increase the radius by 0.5 and generate a circle collider*\n\
@@ -70,7 +70,7 @@ fn main() {
     let msg = game.add_text("msg", "See console output for instructions.");
     msg.translation = Vec2::new(0.0, -325.0);
 
-    // Text to let the user know whether or not their polygon is convex
+    // Text to let the user know whether their polygon is convex, concave, or invalid
     let convex = game.add_text("convex", "???");
     convex.translation = Vec2::new(0.0, 325.0);
 
@@ -155,18 +155,19 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
     ]) {
         sprite.collider = Collider::circle(game_state.circle_radius);
     }
-    // Let the user know whether or not their collider is currently convex
+    // Let the user know whether their collider is currently convex, concave (which will be split
+    // into convex parts), or invalid
     let convex = engine_state.texts.get_mut("convex").unwrap();
-    const CONVEX_MESSAGE: &str = "Convex!";
-    const NOT_CONVEX_MESSAGE: &str = "Not a convex polygon. :-(";
-    if sprite.collider.is_convex() {
-        if convex.value != CONVEX_MESSAGE {
-            convex.value = CONVEX_MESSAGE.into();
-        }
+    let message = if sprite.collider.is_convex() {
+        "Convex!".to_string()
     } else {
-        if convex.value != NOT_CONVEX_MESSAGE {
-            convex.value = NOT_CONVEX_MESSAGE.into();
+        match sprite.collider.convex_parts().len() {
+            0 | 1 => "Not a valid polygon. :-(".to_string(),
+            parts => format!("Concave, split into {} convex parts.", parts),
         }
+    };
+    if convex.value != message {
+        convex.value = message;
     }
     // Write the collider file
     if engine_state.keyboard_state.just_pressed(KeyCode::W) {
@@ -48,6 +48,7 @@ fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
                 text.value = format!("{:?}", collision_event.pair);
                 engine_state.audio_manager.play_sfx(SfxPreset::Switch1, 1.0)
             }
+            CollisionState::Ongoing => {}
             CollisionState::End => {
                 text.value = "".into();
                 engine_state.audio_manager.play_sfx(SfxPreset::Switch2, 1.0)
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use rusty_engine::prelude::*;
+
+struct GameState {
+    frames: u32,
+}
+
+rusty_engine::init!(GameState);
+
+fn main() {
+    let mut game = Game::new();
+    let race_car = game.add_sprite("Race Car", SpritePreset::RacingCarGreen);
+    race_car.translation = Vec2::new(-200.0, 0.0);
+
+    game.add_logic(logic);
+
+    // Run for 60 frames of exactly 1/60th of a second each, without opening a window
+    let game_state = game.run_headless(
+        GameState { frames: 0 },
+        60,
+        Duration::from_secs_f64(1.0 / 60.0),
+    );
+
+    // Since time advanced by exactly one second, the car should have moved exactly 100 pixels
+    let race_car = game.sprites.get("Race Car").unwrap();
+    println!(
+        "After {} frames the race car is at {}",
+        game_state.frames, race_car.translation
+    );
+    assert!((race_car.translation.x - -100.0).abs() < 0.01);
+}
+
+fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    game_state.frames += 1;
+    let race_car = engine_state.sprites.get_mut("Race Car").unwrap();
+    race_car.translation.x += 100.0 * engine_state.delta_f32;
+    true
+}
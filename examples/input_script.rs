@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use rusty_engine::prelude::*;
+
+#[derive(Default)]
+struct GameState {
+    honks: u32,
+}
+
+rusty_engine::init!(GameState);
+
+fn main() {
+    let mut game = Game::new();
+    let _ = game.add_sprite("Race Car", SpritePreset::RacingCarGreen);
+
+    // Hold the right arrow for half a second, then honk twice with the space bar
+    let mut input_script = InputScript::new();
+    input_script
+        .add(0, InputAction::KeyPress(KeyCode::Right))
+        .add(30, InputAction::KeyRelease(KeyCode::Right))
+        .add(40, InputAction::KeyPress(KeyCode::Space))
+        .add(41, InputAction::KeyRelease(KeyCode::Space))
+        .add(50, InputAction::KeyPress(KeyCode::Space))
+        .add(51, InputAction::KeyRelease(KeyCode::Space));
+    game.input_script(input_script);
+
+    game.add_logic(logic);
+    let game_state = game.run_headless(
+        GameState::default(),
+        60,
+        Duration::from_secs_f64(1.0 / 60.0),
+    );
+
+    let race_car = game.sprites.get("Race Car").unwrap();
+    println!(
+        "The race car honked {} times and ended up at {}",
+        game_state.honks, race_car.translation
+    );
+    assert_eq!(game_state.honks, 2);
+    assert!((race_car.translation.x - 100.0).abs() < 0.01);
+}
+
+fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    if engine_state.keyboard_state.just_pressed(KeyCode::Space) {
+        game_state.honks += 1;
+    }
+    let race_car = engine_state.sprites.get_mut("Race Car").unwrap();
+    if engine_state.keyboard_state.pressed(KeyCode::Right) {
+        race_car.translation.x += 200.0 * engine_state.delta_f32;
+    }
+    true
+}
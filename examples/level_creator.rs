@@ -193,35 +193,18 @@ fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
 
     // Change sprite to prev/next preset
     if prev_preset || next_preset {
-        let old_sprite = {
-            engine_state
-                .sprites
-                .get_mut(&game_state.current_label)
-                .unwrap()
-                .clone()
-        };
+        let sprite = engine_state
+            .sprites
+            .get_mut(&game_state.current_label)
+            .unwrap();
         let (idx, _) = SpritePreset::variant_iter()
             .enumerate()
-            .find(|(_, preset)| preset.filepath() == old_sprite.filepath)
+            .find(|(_, preset)| preset.filepath() == sprite.filepath)
             .unwrap();
         let new_idx = (idx + 1) % SpritePreset::variant_iter().count();
         let new_preset = SpritePreset::variant_iter().nth(new_idx).unwrap();
-
-        let new_label = game_state.next_sprite_num.to_string();
-        game_state.next_sprite_num += 1;
-        let mut new_sprite = Sprite::new(new_label.clone(), new_preset);
-
-        game_state.current_label = new_label;
-        new_sprite.layer = MAX_LAYER;
-        new_sprite.translation = old_sprite.translation;
-        new_sprite.rotation = old_sprite.rotation;
-        new_sprite.scale = old_sprite.scale;
-        engine_state
-            .sprites
-            .insert(new_sprite.label.clone(), new_sprite);
-        engine_state
-            .sprites
-            .remove::<str>(old_sprite.label.as_ref());
+        // Changing the filepath swaps the image and loads the matching collider
+        sprite.filepath = new_preset.into();
         println!("{:?}", new_preset);
     }
 
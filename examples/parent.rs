@@ -0,0 +1,66 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!();
+
+const TURN_SPEED: f32 = 3.0;
+const DRIVE_SPEED: f32 = 250.0;
+
+fn main() {
+    let mut game = Game::new();
+
+    let car = game.add_sprite("car", SpritePreset::RacingCarBlue);
+    car.layer = 1.0;
+
+    // The barrel is attached to the car, so it follows the car around. Its translation is relative
+    // to the car, and it spins on its own on top of the car's rotation.
+    let barrel = game.add_sprite("barrel", SpritePreset::RacingBarrelRed);
+    barrel.parent = Some("car".into());
+    barrel.translation = Vec2::new(-20.0, 0.0);
+    barrel.scale = 0.5;
+    barrel.layer = 2.0;
+    barrel.angular_velocity = 2.0;
+
+    // Text can be attached to a sprite too
+    let name_tag = game.add_text("name_tag", "Player 1");
+    name_tag.parent = Some("car".into());
+    name_tag.translation = Vec2::new(0.0, 50.0);
+    name_tag.font_size = 20.0;
+
+    let msg = game.add_text(
+        "instructions",
+        "Drive with the arrow keys. Press Delete to remove the car, and everything attached to it.",
+    );
+    msg.font_size = 20.0;
+    msg.translation.y = 330.0;
+
+    game.add_logic(logic);
+    game.run(());
+}
+
+fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
+    let keyboard_state = &engine_state.keyboard_state;
+    if keyboard_state.just_pressed(KeyCode::Delete) {
+        // Removing the car removes the barrel and the name tag as well
+        engine_state.sprites.remove("car");
+        return true;
+    }
+    if let Some(car) = engine_state.sprites.get_mut("car") {
+        let mut turn = 0.0;
+        if keyboard_state.pressed(KeyCode::Left) {
+            turn += 1.0;
+        }
+        if keyboard_state.pressed(KeyCode::Right) {
+            turn -= 1.0;
+        }
+        let mut drive = 0.0;
+        if keyboard_state.pressed(KeyCode::Up) {
+            drive += 1.0;
+        }
+        if keyboard_state.pressed(KeyCode::Down) {
+            drive -= 1.0;
+        }
+        car.angular_velocity = turn * TURN_SPEED;
+        car.velocity = Vec2::new(car.rotation.cos(), car.rotation.sin()) * drive * DRIVE_SPEED;
+    }
+    true
+}
@@ -78,19 +78,14 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
             marble.translation.x = player_x;
             marble.layer = 5.0;
             marble.collision = true;
+            marble.velocity = Vec2::new(0.0, MARBLE_SPEED);
+            // Marbles only hit cars, never each other
+            marble.collision_group = "marble".into();
+            marble.collision_mask = vec!["car".into()];
             engine_state.audio_manager.play_sfx(SfxPreset::Impact2, 0.7);
         }
     }
 
-    // Move marbles
-    for marble in engine_state
-        .sprites
-        .values_mut()
-        .filter(|marble| marble.label.starts_with("marble"))
-    {
-        marble.translation.y += MARBLE_SPEED * engine_state.delta_f32;
-    }
-
     // Clean up sprites that have gone off the screen
     let mut labels_to_delete = vec![];
     for sprite in engine_state.sprites.values_mut() {
@@ -105,15 +100,6 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
         }
     }
 
-    // Move cars across the screen
-    for car in engine_state
-        .sprites
-        .values_mut()
-        .filter(|car| car.label.starts_with("car"))
-    {
-        car.translation.x += CAR_SPEED * engine_state.delta_f32;
-    }
-
     // Spawn cars
     if game_state
         .spawn_timer
@@ -145,6 +131,8 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
             car.translation.x = -740.0;
             car.translation.y = thread_rng().gen_range(-100.0..325.0);
             car.collision = true;
+            car.velocity = Vec2::new(CAR_SPEED, 0.0);
+            car.collision_group = "car".into();
         }
     }
 
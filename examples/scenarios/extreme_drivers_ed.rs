@@ -5,7 +5,6 @@ struct GameState {
     win_amount: u32,
     won: bool,
     crashed: bool,
-    velocity: Vec2,
 }
 
 rusty_engine::init!(GameState);
@@ -13,6 +12,18 @@ rusty_engine::init!(GameState);
 fn main() {
     let mut game = Game::new();
 
+    // Pass `--record some_file.ron` to record a run, or `--replay some_file.ron` to replay one
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.as_slice() {
+        [flag, filepath] if flag == "--record" => {
+            game.record_input(filepath);
+        }
+        [flag, filepath] if flag == "--replay" => {
+            game.replay_input(filepath);
+        }
+        _ => {}
+    }
+
     // "level" generated using the Rusty Engine level creator example, and then tweaked
     let player = game.add_sprite("player", SpritePreset::RacingCarYellow);
     player.translation = Vec2::new(-9.0, 23.0);
@@ -20,6 +31,7 @@ fn main() {
     player.scale = 0.5;
     player.layer = 100.0;
     player.collision = true;
+    player.max_speed = Some(MAX_SPEED);
 
     let a = game.add_sprite("82", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(131.7, -155.7);
@@ -33,6 +45,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.66999966;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("88", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(333.6, -214.1);
     a.rotation = -18.84955978;
@@ -51,6 +64,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.76999956;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("247", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-235.1, 3.2);
     a.rotation = -37.69910812;
@@ -63,18 +77,21 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.26999915;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny217", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(314.6, 73.1);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.88999945;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny275", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-121.7, 67.1);
     a.rotation = -54.97783279;
     a.scale = 0.39806983;
     a.layer = 1.24999917;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("97", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(389.6, -241.7);
     a.rotation = -18.84955978;
@@ -159,6 +176,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.84999949;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("101", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(471.8, -190.8);
     a.rotation = -18.84955978;
@@ -171,12 +189,14 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.15999925;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny276", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-121.0, 182.4);
     a.rotation = -54.97783279;
     a.scale = 0.39806983;
     a.layer = 1.25999916;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("238", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-514.0, 22.3);
     a.rotation = -22.77655220;
@@ -213,6 +233,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.20999920;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("67", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(179.0, -105.3);
     a.rotation = -18.84955978;
@@ -225,6 +246,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.77999955;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("16", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(-75.9, 44.2);
     a.rotation = -1.57079637;
@@ -249,12 +271,14 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.74999958;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny274", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-153.1, -40.5);
     a.rotation = -54.97783279;
     a.scale = 0.39806983;
     a.layer = 1.23999918;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("66", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(129.5, -105.8);
     a.rotation = -18.84955978;
@@ -273,6 +297,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.83999950;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("70", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(328.3, -105.8);
     a.rotation = -18.84955978;
@@ -303,12 +328,14 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.67999965;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny216", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(319.8, 233.2);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.87999946;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("80", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(75.9, -186.7);
     a.rotation = -18.84955978;
@@ -321,6 +348,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.80999953;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("93", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(218.2, -309.5);
     a.rotation = -18.84955978;
@@ -339,12 +367,14 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.18999922;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny210", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(377.4, -26.8);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.81999952;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("90", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(109.8, -298.1);
     a.rotation = -18.84955978;
@@ -363,6 +393,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.68999964;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("91", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(139.7, -288.1);
     a.rotation = -18.84955978;
@@ -393,6 +424,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.17999923;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("89", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(79.4, -313.9);
     a.rotation = -18.84955978;
@@ -417,6 +449,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.27999914;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("86", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(265.5, -173.4);
     a.rotation = -18.84955978;
@@ -441,6 +474,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.73999959;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("245", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-447.9, 264.2);
     a.rotation = -29.84513855;
@@ -459,6 +493,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.16999924;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("250", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-174.0, 121.7);
     a.rotation = -51.83624649;
@@ -471,12 +506,14 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.19999921;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny215", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(483.7, 283.9);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.86999947;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("87", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(297.6, -194.3);
     a.rotation = -18.84955978;
@@ -495,6 +532,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.21999919;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("28", SpritePreset::RollingBlockCorner);
     a.translation = Vec2::new(-115.6, -146.2);
     a.rotation = -9.42477798;
@@ -507,6 +545,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.12999928;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("17", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(56.7, 44.9);
     a.rotation = -1.57079637;
@@ -573,12 +612,14 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.82999951;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny200", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-602.2, -188.1);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.71999961;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("81", SpritePreset::RacingConeStraight);
     a.translation = Vec2::new(99.5, -169.2);
     a.rotation = -18.84955978;
@@ -597,6 +638,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.28999913;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("48", SpritePreset::RollingBlockNarrow);
     a.translation = Vec2::new(-75.8, -231.7);
     a.rotation = -10.99557495;
@@ -621,6 +663,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.69999963;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("254", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-233.8, 287.1);
     a.rotation = -56.54862595;
@@ -645,18 +688,21 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.14999926;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny201", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-78.5, -321.2);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.72999960;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny214", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(578.1, 220.8);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.85999948;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("252", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-173.5, 227.3);
     a.rotation = -51.83624649;
@@ -669,12 +715,14 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.89999944;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny204", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(102.3, -235.8);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.75999957;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("126", SpritePreset::RollingBlockSquare);
     a.translation = Vec2::new(-600.7, -319.5);
     a.rotation = -20.42035675;
@@ -687,6 +735,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.65999967;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("65", SpritePreset::RacingBarrierRed);
     a.translation = Vec2::new(79.9, -105.5);
     a.rotation = -18.84955978;
@@ -729,6 +778,7 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.70999962;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("240", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-531.0, 124.2);
     a.rotation = -23.56195068;
@@ -741,18 +791,21 @@ fn main() {
     a.scale = 0.39820361;
     a.layer = 0.78999954;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny208", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(582.9, -256.5);
     a.rotation = -21.99115372;
     a.scale = 0.39820361;
     a.layer = 0.79999954;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("shiny273", SpritePreset::RollingHoleStart);
     a.translation = Vec2::new(-256.7, -64.1);
     a.rotation = -54.97783279;
     a.scale = 0.39806983;
     a.layer = 1.22999918;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("140", SpritePreset::RollingBallRedAlt);
     a.translation = Vec2::new(455.0, 197.4);
     a.rotation = -21.99115372;
@@ -783,6 +836,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.13999927;
     a.collision = true;
+    a.sensor = true;
     let a = game.add_sprite("236", SpritePreset::RacingBarrierWhite);
     a.translation = Vec2::new(-411.3, 3.2);
     a.rotation = -21.99115372;
@@ -801,6 +855,7 @@ fn main() {
     a.scale = 0.39806983;
     a.layer = 1.11999929;
     a.collision = true;
+    a.sensor = true;
 
     // Music!
     game.audio_manager.play_music(MusicPreset::Classy8Bit, 0.5);
@@ -822,7 +877,6 @@ fn main() {
         win_amount,
         won: false,
         crashed: false,
-        velocity: Vec2::ZERO,
     };
 
     game.add_logic(logic);
@@ -831,6 +885,7 @@ fn main() {
 
 const TURN_RATE: f32 = 3.0;
 const ACCELERATION_RATE: f32 = 100.0;
+const MAX_SPEED: f32 = 300.0;
 
 fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
     let score_text = engine_state.texts.get_mut("score_text").unwrap();
@@ -872,14 +927,14 @@ fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
             rotation -= 1.0;
         }
     }
-    let mut velocity_magnitude = game_state.velocity.length();
+    // The engine moves the player by its velocity (capped at its max speed) every frame
+    let mut velocity_magnitude = player.velocity.length();
     velocity_magnitude += (acceleration * ACCELERATION_RATE) * engine_state.delta_f32;
     player.rotation += (rotation * TURN_RATE) * engine_state.delta_f32;
-    game_state.velocity = Vec2::new(
+    player.velocity = Vec2::new(
         velocity_magnitude * player.rotation.cos(),
         velocity_magnitude * player.rotation.sin(),
     );
-    player.translation += game_state.velocity * engine_state.delta_f32;
 
     // Make the shinies...shinier
     for sprite in engine_state
@@ -926,6 +981,7 @@ fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
 
         // Crash!
         game_state.crashed = true;
+        engine_state.sprites.get_mut("player").unwrap().velocity = Vec2::ZERO;
         //game_state.add_text("crashed", "You crashed. You fail. :-(");
         engine_state.audio_manager.play_sfx(SfxPreset::Jingle3, 1.0);
         engine_state.audio_manager.stop_music();
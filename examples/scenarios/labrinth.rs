@@ -0,0 +1,92 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!(GameState);
+
+const ROLL_ACCELERATION: f32 = 500.0;
+// The tiles in `rolling/tiles.png`
+const HOLE: usize = 2;
+const START: usize = 3;
+const END: usize = 4;
+
+struct GameState {
+    start: Vec2,
+    won: bool,
+}
+
+fn main() {
+    let mut game = Game::new();
+
+    game.window_settings(WindowDescriptor {
+        title: "Labrinth".into(),
+        ..Default::default()
+    });
+
+    // The whole maze is a single tilemap. The wall tiles are solid, so the marble can't roll
+    // through them.
+    let tilemap =
+        game.add_tilemap(Tilemap::from_file("labrinth", "rolling/labrinth.tilemap").unwrap());
+    tilemap.translation.y = -30.0;
+    let (column, row) = tilemap.cells_with(START)[0];
+    let start = tilemap.cell_center(column, row);
+
+    let marble = game.add_sprite("marble", SpritePreset::RollingBallBlue);
+    marble.translation = start;
+    marble.layer = 1.0;
+    marble.collision = true;
+    marble.body_type = BodyType::Dynamic;
+    marble.restitution = 0.3;
+    marble.drag = 0.5;
+
+    let msg = game.add_text(
+        "message",
+        "Roll the marble to the end with the arrow keys. Don't fall in the holes!",
+    );
+    msg.font_size = 24.0;
+    msg.translation.y = 335.0;
+
+    game.add_logic(game_logic);
+    game.run(GameState { start, won: false });
+}
+
+fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    if game_state.won {
+        return true;
+    }
+
+    // Tilt the labrinth to roll the marble
+    let keyboard_state = &engine_state.keyboard_state;
+    let mut tilt = Vec2::ZERO;
+    if keyboard_state.pressed(KeyCode::Left) {
+        tilt.x -= 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Right) {
+        tilt.x += 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Up) {
+        tilt.y += 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Down) {
+        tilt.y -= 1.0;
+    }
+    let marble = engine_state.sprites.get_mut("marble").unwrap();
+    marble.acceleration = tilt.normalize_or_zero() * ROLL_ACCELERATION;
+
+    // See what the marble is rolling over
+    let tilemap = &engine_state.tilemaps["labrinth"];
+    let tile = tilemap
+        .cell_at(marble.translation)
+        .and_then(|(column, row)| tilemap.tile(column, row));
+    if tile == Some(HOLE) {
+        // Start over
+        marble.translation = game_state.start;
+        marble.velocity = Vec2::ZERO;
+        engine_state.audio_manager.play_sfx(SfxPreset::Impact3, 0.5);
+    } else if tile == Some(END) {
+        marble.velocity = Vec2::ZERO;
+        marble.acceleration = Vec2::ZERO;
+        game_state.won = true;
+        engine_state.texts.get_mut("message").unwrap().value = "You made it!".into();
+        engine_state.audio_manager.play_sfx(SfxPreset::Jingle1, 0.5);
+    }
+    true
+}
@@ -4,9 +4,13 @@ use SpritePreset::*; // The SpritePreset enum was imported from rusty_engine::pr
 
 const ROAD_SPEED: f32 = 400.0;
 const PLAYER_SPEED: f32 = 250.0;
+const DAMAGE_FLASH_TIME: f32 = 0.25;
+const DAMAGE_NUMBER_MAX_SCALE: f32 = 3.0;
 
 struct GameState {
     health_amount: u8,
+    damage_flash: f32,
+    damage_number_count: u32,
 }
 
 rusty_engine::init!(GameState);
@@ -14,11 +18,12 @@ rusty_engine::init!(GameState);
 fn main() {
     let mut game = Game::new();
 
-    // Create the player sprite
+    // Create the player sprite. The player drives down the road, and the camera keeps up.
     let player1 = game.add_sprite("player1", RacingCarBlue);
     player1.translation.x = -500.0;
     player1.layer = 10.0;
     player1.collision = true;
+    player1.velocity.x = ROAD_SPEED;
 
     // Start some background music
     game.audio_manager
@@ -51,15 +56,21 @@ fn main() {
         sprite.translation.y = thread_rng().gen_range(-300.0..300.0);
     }
 
-    // Create the health message
+    // Create the health message in the top-right corner of the screen, where it stays even as the
+    // camera scrolls
     let health_message = game.add_text("health_message", "Health: 5");
-    health_message.translation = Vec2::new(550.0, 320.0);
+    health_message.screen_anchor = Some(ScreenAnchor::TopRight);
+    health_message.translation = Vec2::new(-90.0, -40.0);
 
     game.add_logic(lose_condition);
     game.add_logic(game_logic);
 
     // Run the game, which will run our game logic functions once every frame
-    game.run(GameState { health_amount: 5 });
+    game.run(GameState {
+        health_amount: 5,
+        damage_flash: 0.0,
+        damage_number_count: 0,
+    });
 }
 
 fn lose_condition(_: &mut EngineState, game_state: &mut GameState) -> bool {
@@ -90,25 +101,45 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
     if player1.translation.y < -360.0 || player1.translation.y > 360.0 {
         game_state.health_amount = 0;
     }
+    // Flash red for a moment after taking damage
+    game_state.damage_flash -= engine_state.delta_f32;
+    player1.tint = if game_state.damage_flash > 0.0 {
+        Color::RED
+    } else {
+        Color::WHITE
+    };
 
-    // Move road objects
+    let player1_translation = player1.translation;
+
+    // Keep the camera just ahead of player1
+    let camera_x = player1.translation.x + 500.0;
+    engine_state.camera.translation.x = camera_x;
+
+    // Road objects that have scrolled off the left side of the screen are moved back ahead
     for sprite in engine_state.sprites.values_mut() {
-        if sprite.label.starts_with("roadline") {
-            sprite.translation.x -= ROAD_SPEED * engine_state.delta_f32;
-            if sprite.translation.x < -675.0 {
-                sprite.translation.x += 1500.0;
-            }
+        if sprite.label.starts_with("roadline") && sprite.translation.x < camera_x - 675.0 {
+            sprite.translation.x += 1500.0;
+        }
+        if sprite.label.starts_with("obstacle") && sprite.translation.x < camera_x - 800.0 {
+            sprite.translation.x = camera_x + thread_rng().gen_range(800.0..1600.0);
+            sprite.translation.y = thread_rng().gen_range(-300.0..300.0);
         }
-        if sprite.label.starts_with("obstacle") {
-            sprite.translation.x -= ROAD_SPEED * engine_state.delta_f32;
-            if sprite.translation.x < -800.0 {
-                sprite.translation.x = thread_rng().gen_range(800.0..1600.0);
-                sprite.translation.y = thread_rng().gen_range(-300.0..300.0);
-            }
+    }
+
+    // Damage numbers float up, grow, and spin until they are big enough to go away
+    for text in engine_state.texts.values_mut() {
+        if text.label.starts_with("damage") {
+            text.translation.y += 60.0 * engine_state.delta_f32;
+            text.scale += 1.5 * engine_state.delta_f32;
+            text.rotation += 4.0 * engine_state.delta_f32;
         }
     }
+    engine_state
+        .texts
+        .retain(|label, text| !label.starts_with("damage") || text.scale < DAMAGE_NUMBER_MAX_SCALE);
 
     // Deal with collisions
+    let mut damaged = false;
     let health_message = engine_state.texts.get_mut("health_message").unwrap();
     for event in engine_state.collision_events.drain(..) {
         // We don't care if obstacles collide with each other or collisions end
@@ -118,12 +149,24 @@ fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> boo
         if game_state.health_amount > 0 {
             game_state.health_amount -= 1;
             health_message.value = format!("Health: {}", game_state.health_amount);
+            game_state.damage_flash = DAMAGE_FLASH_TIME;
+            engine_state.camera.shake(8.0, DAMAGE_FLASH_TIME);
             engine_state.audio_manager.play_sfx(SfxPreset::Impact3, 0.5);
+            damaged = true;
         }
     }
+    if damaged {
+        let label = format!("damage{}", game_state.damage_number_count);
+        game_state.damage_number_count += 1;
+        let damage_number = engine_state.add_text(label, "-1");
+        damage_number.translation = player1_translation + Vec2::new(0.0, 40.0);
+        damage_number.color = Color::RED;
+    }
     if game_state.health_amount == 0 {
         let game_over = engine_state.add_text("game over", "Game Over");
         game_over.font_size = 128.0;
+        game_over.screen_anchor = Some(ScreenAnchor::Center);
+        engine_state.sprites.get_mut("player1").unwrap().velocity = Vec2::ZERO;
         engine_state.audio_manager.stop_music();
         engine_state.audio_manager.play_sfx(SfxPreset::Jingle3, 0.5);
     }
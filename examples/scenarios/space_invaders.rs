@@ -0,0 +1,152 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!(GameState);
+
+const PLAYER_SPEED: f32 = 400.0;
+const BULLET_SPEED: f32 = 700.0;
+const ALIEN_STEP_DOWN: f32 = 30.0;
+const ALIEN_COLUMNS: usize = 10;
+const ALIEN_ROWS: usize = 4;
+
+struct GameState {
+    // 1.0 while the aliens march right, -1.0 while they march left
+    alien_direction: f32,
+    bullet_count: u32,
+    game_over: bool,
+}
+
+fn main() {
+    let mut game = Game::new();
+
+    game.window_settings(WindowDescriptor {
+        title: "Space Invaders".into(),
+        ..Default::default()
+    });
+
+    // The player is a trusty green triangle. Shapes don't need any art!
+    let player = game.add_shape(
+        "player",
+        SpriteShape::regular_polygon(3, 30.0, Color::GREEN).with_outline(Color::WHITE, 3.0),
+    );
+    player.rotation = UP;
+    player.translation.y = -320.0;
+    player.collision = true;
+    player.collision_group = "player".into();
+
+    // A grid of aliens near the top of the screen
+    for row in 0..ALIEN_ROWS {
+        for column in 0..ALIEN_COLUMNS {
+            let alien = game.add_shape(
+                format!("alien{}", row * ALIEN_COLUMNS + column),
+                SpriteShape::regular_polygon(6, 22.0, Color::PURPLE).with_outline(Color::PINK, 3.0),
+            );
+            alien.translation = Vec2::new(-450.0 + 80.0 * column as f32, 300.0 - 60.0 * row as f32);
+            alien.rotation = UP;
+            alien.collision = true;
+            alien.collision_group = "alien".into();
+        }
+    }
+
+    let msg = game.add_text("message", "Move with Left and Right. Shoot with Space.");
+    msg.font_size = 20.0;
+    msg.translation.y = -360.0;
+
+    game.add_logic(game_logic);
+    game.run(GameState {
+        alien_direction: 1.0,
+        bullet_count: 0,
+        game_over: false,
+    });
+}
+
+fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    if game_state.game_over {
+        return true;
+    }
+
+    // Move and shoot
+    let keyboard_state = &engine_state.keyboard_state;
+    let mut direction = 0.0;
+    if keyboard_state.pressed(KeyCode::Left) {
+        direction -= 1.0;
+    }
+    if keyboard_state.pressed(KeyCode::Right) {
+        direction += 1.0;
+    }
+    let shoot = keyboard_state.just_pressed(KeyCode::Space);
+    let player = engine_state.sprites.get_mut("player").unwrap();
+    player.translation.x = (player.translation.x
+        + direction * PLAYER_SPEED * engine_state.delta_f32)
+        .clamp(-600.0, 600.0);
+    let player_x = player.translation.x;
+    if shoot {
+        let label = format!("bullet{}", game_state.bullet_count);
+        game_state.bullet_count += 1;
+        let bullet = engine_state.add_shape(
+            label,
+            SpriteShape::line((0.0, -10.0), (0.0, 10.0), 4.0, Color::YELLOW),
+        );
+        bullet.translation = Vec2::new(player_x, -280.0);
+        bullet.velocity = Vec2::new(0.0, BULLET_SPEED);
+        bullet.collision = true;
+        // Bullets only hit aliens
+        bullet.collision_group = "bullet".into();
+        bullet.collision_mask = vec!["alien".into()];
+    }
+
+    // Shot aliens disappear, along with the bullets that hit them
+    for event in engine_state.collision_events.drain(..) {
+        if event.state.is_end() {
+            continue;
+        }
+        if event.pair.either_starts_with("player") {
+            game_state.game_over = true;
+        } else {
+            engine_state.sprites.remove(&event.pair.0);
+            engine_state.sprites.remove(&event.pair.1);
+        }
+    }
+
+    // Clean up bullets that have gone off the screen
+    engine_state
+        .sprites
+        .retain(|label, sprite| !label.starts_with("bullet") || sprite.translation.y < 400.0);
+
+    // The aliens march back and forth, stepping down each time they reach an edge. The fewer
+    // aliens there are, the faster they go.
+    let alien_count = engine_state
+        .sprites
+        .keys()
+        .filter(|label| label.starts_with("alien"))
+        .count();
+    let alien_speed = 40.0 + 1600.0 / (alien_count as f32 + 1.0);
+    let step = game_state.alien_direction * alien_speed * engine_state.delta_f32;
+    let mut reached_edge = false;
+    for sprite in engine_state.sprites.values_mut() {
+        if sprite.label.starts_with("alien") {
+            sprite.translation.x += step;
+            reached_edge |= sprite.translation.x.abs() > 600.0;
+            if sprite.translation.y < -300.0 {
+                game_state.game_over = true;
+            }
+        }
+    }
+    if reached_edge {
+        game_state.alien_direction = -game_state.alien_direction;
+        for sprite in engine_state.sprites.values_mut() {
+            if sprite.label.starts_with("alien") {
+                sprite.translation.y -= ALIEN_STEP_DOWN;
+            }
+        }
+    }
+
+    // Win or lose
+    let message = engine_state.texts.get_mut("message").unwrap();
+    if game_state.game_over {
+        message.value = "The aliens got you! Game over.".into();
+    } else if alien_count == 0 {
+        message.value = "You saved the world!".into();
+        game_state.game_over = true;
+    }
+    true
+}
@@ -0,0 +1,51 @@
+use rusty_engine::prelude::*;
+
+rusty_engine::init!();
+
+fn main() {
+    let mut game = Game::new();
+
+    // Shapes don't need any image or collider files. Each one gets a collider that matches it.
+    let shapes = [
+        SpriteShape::rectangle((120.0, 80.0), Color::ORANGE),
+        SpriteShape::circle(50.0, Color::CYAN).with_outline(Color::WHITE, 4.0),
+        SpriteShape::regular_polygon(3, 60.0, Color::GREEN),
+        SpriteShape::regular_polygon(6, 55.0, Color::PURPLE).with_outline(Color::PINK, 6.0),
+        SpriteShape::line((-50.0, -30.0), (50.0, 30.0), 12.0, Color::YELLOW),
+        SpriteShape::rectangle((100.0, 100.0), Color::NONE)
+            .with_outline(Color::RED, 5.0)
+            .without_fill(),
+    ];
+    for (i, shape) in shapes.into_iter().enumerate() {
+        let sprite = game.add_shape(format!("shape{}", i), shape);
+        sprite.translation = Vec2::new(-500.0 + 200.0 * i as f32, 0.0);
+        sprite.angular_velocity = 0.5;
+    }
+
+    let msg = game.add_text(
+        "instructions",
+        "Press C to toggle the colliders. Press Space to change the first shape.",
+    );
+    msg.font_size = 20.0;
+    msg.translation.y = 300.0;
+
+    game.add_logic(logic);
+    game.run(());
+}
+
+fn logic(engine_state: &mut EngineState, _: &mut ()) -> bool {
+    if engine_state.keyboard_state.just_pressed(KeyCode::C) {
+        engine_state.debug_sprite_colliders = !engine_state.debug_sprite_colliders;
+    }
+    // Changing a sprite's shape redraws it, and gives it a new collider to match
+    if engine_state.keyboard_state.just_pressed(KeyCode::Space) {
+        let sprite = engine_state.sprites.get_mut("shape0").unwrap();
+        sprite.shape = match sprite.shape.as_ref().map(|shape| &shape.geometry) {
+            Some(ShapeGeometry::Rectangle { .. }) => {
+                Some(SpriteShape::regular_polygon(5, 60.0, Color::ORANGE))
+            }
+            _ => Some(SpriteShape::rectangle((120.0, 80.0), Color::ORANGE)),
+        };
+    }
+    true
+}
@@ -8,10 +8,23 @@ rusty_engine::init!(GameState);
 
 fn main() {
     let mut game = Game::new();
-    let fps = game.add_text("fps", "FPS: ");
+    // A text can be made of several sections, each with its own style
+    let fps = game.add_text("fps", "");
     fps.translation = Vec2::new(0.0, 250.0);
     fps.font = "FiraMono-Medium.ttf".to_string();
     fps.font_size = 60.0;
+    fps.sections = vec![
+        TextSection::new("FPS: "),
+        TextSection::new("").with_color(Color::YELLOW),
+    ];
+
+    // Left-aligned text starts at its translation and extends to the right
+    let aligned = game.add_text("aligned", "This text is left-aligned\nand green.");
+    aligned.translation = Vec2::new(-600.0, 320.0);
+    aligned.horizontal_align = HorizontalAlign::Left;
+    aligned.vertical_align = VerticalAlign::Top;
+    aligned.color = Color::GREEN;
+    aligned.font_size = 20.0;
 
     let zoom_msg = game.add_text(
         "zoom_msg",
@@ -28,11 +41,11 @@ fn main() {
     font_msg.font = "FiraMono-Medium.ttf".to_string();
     font_msg.translation.y = 0.0;
 
-    let msg = game.add_text("msg", "Changing the text's translation, rotation*, and scale* is fast,\n so feel free to do that a lot.");
+    let msg = game.add_text("msg", "Changing the text's translation, rotation, and scale is fast,\n so feel free to do that a lot.");
     msg.font_size = 24.0;
     msg.translation.y = -150.0;
 
-    let msg2 = game.add_text("msg2", "*Changing rotation and scale will not work until Bevy 0.6 is released,\nbut changing the translation works great already!");
+    let msg2 = game.add_text("msg2", "Like this!");
     msg2.font_size = 20.0;
 
     let game_state = GameState {
@@ -45,12 +58,14 @@ fn main() {
 fn game_logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
     if game_state.timer.tick(engine_state.delta).just_finished() {
         let mut fps = engine_state.texts.get_mut("fps").unwrap();
-        fps.value = format!("FPS: {:.1}", 1.0 / engine_state.delta_f32);
+        fps.sections[1].value = format!("{:.1}", 1.0 / engine_state.delta_f32);
     }
 
     let msg2 = engine_state.texts.get_mut("msg2").unwrap();
     msg2.translation.x = 50.0 * (engine_state.time_since_startup_f64 * 0.5).sin() as f32;
     msg2.translation.y = 50.0 * (engine_state.time_since_startup_f64 * 0.5).cos() as f32 - 275.0;
+    msg2.rotation += engine_state.delta_f32;
+    msg2.scale = 1.5 + 0.5 * (engine_state.time_since_startup_f64 * 2.0).sin() as f32;
 
     let msg3 = engine_state.texts.get_mut("zoom_msg").unwrap();
     msg3.font_size = 10.0 * (engine_state.time_since_startup_f64 * 0.5).cos() as f32 + 25.0;
@@ -0,0 +1,76 @@
+use rusty_engine::prelude::*;
+
+const PADDING: f32 = 20.0;
+const LINES: [&str; 4] = [
+    "Welcome, traveler! The road ahead is long, and the barrels along the way are surprisingly grumpy.",
+    "Long lines of text wrap onto the next line when they get wider than the text's max width, so they always fit in the box.",
+    "The box behind this text is sized from the text's measured size.",
+    "Bye!",
+];
+
+struct GameState {
+    line: usize,
+}
+
+rusty_engine::init!(GameState);
+
+fn main() {
+    let mut game = Game::new();
+
+    // The dialog text wraps between words to stay within its max width
+    let dialog = game.add_text("dialog", LINES[0]);
+    dialog.font_size = 24.0;
+    dialog.max_width = Some(400.0);
+
+    // The box is resized to fit the text once the text has been laid out
+    let dialog_box = game.add_shape(
+        "dialog_box",
+        SpriteShape::rectangle((440.0, 100.0), Color::MIDNIGHT_BLUE)
+            .with_outline(Color::WHITE, 3.0),
+    );
+    dialog_box.layer = 800.0;
+
+    let title = game.add_text("title", "Old Mechanic");
+    title.color = Color::YELLOW;
+
+    let msg = game.add_text(
+        "instructions",
+        "Press Space for the next line. Use the Left and Right arrows to change the max width.",
+    );
+    msg.font_size = 20.0;
+    msg.translation.y = -300.0;
+
+    game.add_logic(logic);
+    game.run(GameState { line: 0 });
+}
+
+fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    let keyboard_state = &engine_state.keyboard_state;
+    let dialog = engine_state.texts.get_mut("dialog").unwrap();
+    if keyboard_state.just_pressed(KeyCode::Space) {
+        game_state.line = (game_state.line + 1) % LINES.len();
+        dialog.value = LINES[game_state.line].to_string();
+    }
+    let max_width = dialog.max_width.unwrap_or_default();
+    if keyboard_state.pressed(KeyCode::Left) {
+        dialog.max_width = Some((max_width - 200.0 * engine_state.delta_f32).max(100.0));
+    }
+    if keyboard_state.pressed(KeyCode::Right) {
+        dialog.max_width = Some((max_width + 200.0 * engine_state.delta_f32).min(1000.0));
+    }
+    let size = dialog.size;
+    if size == Vec2::ZERO {
+        // The text hasn't been laid out yet
+        return true;
+    }
+
+    // Fit the box around the text, and put the title on top of the box
+    let box_size = size + Vec2::splat(PADDING * 2.0);
+    let dialog_box = engine_state.sprites.get_mut("dialog_box").unwrap();
+    dialog_box.shape = Some(
+        SpriteShape::rectangle(box_size, Color::MIDNIGHT_BLUE).with_outline(Color::WHITE, 3.0),
+    );
+    let title = engine_state.texts.get_mut("title").unwrap();
+    title.translation.y = box_size.y / 2.0 + title.size.y / 2.0 + 10.0;
+    true
+}
@@ -0,0 +1,279 @@
+//! Facilities for animating sprites by flipping through a sequence of images (frames).
+//!
+//! An [`Animation`] is registered once with
+//! [`EngineState::add_animation`](crate::prelude::EngineState::add_animation), and then any number
+//! of sprites can play it with [`Sprite::play_animation`](crate::prelude::Sprite::play_animation).
+//! The engine advances each sprite's animation every frame, after your game logic runs.
+//!
+//! Animations can be built in code, or loaded from a
+//! [Rusty Object Notation (RON)](https://github.com/ron-rs/ron) file that looks like this:
+//!
+//! ```text
+//! (
+//!     frames: [
+//!         (filepath: "rolling/ball_blue.png", duration: 0.25),
+//!         (filepath: "rolling/ball_blue_alt.png", duration: 0.25),
+//!     ],
+//!     mode: PingPong,
+//! )
+//! ```
+use crate::{
+    game::DeltaOverride,
+    prelude::{EngineState, Sprite},
+    shape::ShapeTextures,
+    sprite::SpriteImage,
+};
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+// Frames shorter than this are treated as being this long, so a frame with a zero duration can't
+// spin the animation forever
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ShapeTextures>().add_system(
+            animate_sprites
+                .system()
+                .label("animate_sprites")
+                .after("game_logic_sync"),
+        );
+    }
+}
+
+/// What an [`Animation`] does when it gets to its last frame
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// Start over at the first frame
+    Loop,
+    /// Play the frames in reverse back to the first frame, then forward again, and so on
+    PingPong,
+    /// Stop on the last frame. The animation is then finished.
+    Once,
+}
+
+impl Default for AnimationMode {
+    fn default() -> Self {
+        Self::Loop
+    }
+}
+
+/// A single frame of an [`Animation`]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AnimationFrame {
+    /// The image to show, relative to `assets/sprite` (just like [`Sprite::filepath`])
+    pub filepath: PathBuf,
+    /// How long to show the image, in seconds
+    pub duration: f32,
+}
+
+/// A sequence of frames that sprites can play. See the [module-level docs](crate::animation).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Animation {
+    /// The frames, in the order they are played
+    pub frames: Vec<AnimationFrame>,
+    /// What happens at the end of the frames
+    #[serde(default)]
+    pub mode: AnimationMode,
+}
+
+impl Animation {
+    /// Create an [`Animation`] from a list of image files (or [`SpritePreset`]s), each shown for
+    /// `frame_duration` seconds.
+    ///
+    /// [`SpritePreset`]: crate::prelude::SpritePreset
+    pub fn from_files<I, P>(files: I, frame_duration: f32, mode: AnimationMode) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            frames: files
+                .into_iter()
+                .map(|filepath| AnimationFrame {
+                    filepath: filepath.into(),
+                    duration: frame_duration,
+                })
+                .collect(),
+            mode,
+        }
+    }
+
+    /// Load an [`Animation`] from a RON file. Just like the frames' image files, `filepath` is
+    /// relative to `assets/sprite`. If the file can't be read or parsed, an error is printed and
+    /// `None` is returned.
+    pub fn from_file<P: AsRef<Path>>(filepath: P) -> Option<Self> {
+        let filepath = PathBuf::from("assets/sprite").join(filepath);
+        let fh = match File::open(filepath) {
+            Ok(fh) => fh,
+            Err(e) => {
+                eprintln!("failed to open animation file: {}", e);
+                return None;
+            }
+        };
+        match ron::de::from_reader::<_, Animation>(fh) {
+            Ok(animation) => Some(animation),
+            Err(e) => {
+                eprintln!("failed deserializing animation from file: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The total length of the frames, in seconds. A ping-pong animation takes about twice as long
+    /// to get back to the first frame.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Where a sprite is in the [`Animation`] it is playing. Start one with
+/// [`Sprite::play_animation`](crate::prelude::Sprite::play_animation).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteAnimation {
+    /// READONLY: The name the animation was registered under with
+    /// [`EngineState::add_animation`](crate::prelude::EngineState::add_animation)
+    pub name: String,
+    /// SYNCED: While `true`, the animation stays on the current frame
+    pub paused: bool,
+    /// READONLY: The index of the frame being shown
+    pub frame: usize,
+    /// READONLY: How long the current frame has been shown, in seconds
+    pub elapsed: f32,
+    /// READONLY: Whether a ping-pong animation is currently playing its frames in reverse
+    pub reversed: bool,
+    /// READONLY: Whether a [`AnimationMode::Once`] animation has finished showing its last frame.
+    /// Other animations never finish.
+    pub finished: bool,
+}
+
+impl SpriteAnimation {
+    /// Start playing the animation registered as `name` from its first frame
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            paused: false,
+            frame: 0,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    // Move forward in the animation by `delta` seconds
+    fn advance(&mut self, animation: &Animation, delta: f32) {
+        let length = animation.frames.len();
+        if self.paused || self.finished || length == 0 {
+            return;
+        }
+        // The animation may have been replaced by a shorter one
+        self.frame = self.frame.min(length - 1);
+        self.elapsed += delta;
+        loop {
+            let duration = animation.frames[self.frame]
+                .duration
+                .max(MIN_FRAME_DURATION);
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            match animation.mode {
+                AnimationMode::Loop => self.frame = (self.frame + 1) % length,
+                AnimationMode::Once => {
+                    if self.frame + 1 < length {
+                        self.frame += 1;
+                    } else {
+                        self.elapsed = duration;
+                        self.finished = true;
+                        break;
+                    }
+                }
+                AnimationMode::PingPong => {
+                    if length == 1 {
+                        continue;
+                    }
+                    if self.reversed && self.frame == 0 {
+                        self.reversed = false;
+                    } else if !self.reversed && self.frame + 1 == length {
+                        self.reversed = true;
+                    }
+                    if self.reversed {
+                        self.frame -= 1;
+                    } else {
+                        self.frame += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The image a sprite should be showing right now: the current frame of its animation if it is
+// playing a known one, or its own shape or image otherwise
+#[doc(hidden)]
+pub fn sprite_image(sprite: &Sprite, animations: &HashMap<String, Animation>) -> SpriteImage {
+    let frame = sprite.animation.as_ref().and_then(|sprite_animation| {
+        let animation = animations.get(&sprite_animation.name)?;
+        let last_frame = animation.frames.len().saturating_sub(1);
+        animation.frames.get(sprite_animation.frame.min(last_frame))
+    });
+    match (frame, sprite.shape.as_ref()) {
+        (Some(frame), _) => SpriteImage::File(frame.filepath.clone()),
+        (None, Some(shape)) => SpriteImage::Shape(shape.clone()),
+        (None, None) => SpriteImage::File(sprite.filepath.clone()),
+    }
+}
+
+// system - advance sprite animations, and swap the image of any sprite whose image changed
+// (because of its animation, or because its `filepath` or `shape` was changed)
+#[allow(clippy::too_many_arguments)]
+fn animate_sprites(
+    time: Res<Time>,
+    delta_override: Res<DeltaOverride>,
+    engine_state: Res<EngineState>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut shape_textures: ResMut<ShapeTextures>,
+    mut query: Query<(&mut Sprite, &Handle<ColorMaterial>)>,
+) {
+    let delta = delta_override.delta(&time).as_secs_f32();
+    for (mut sprite, material_handle) in query.iter_mut() {
+        // Paused and finished animations don't go anywhere, so only borrow the sprite mutably
+        // for animations that are actually playing
+        let animation = sprite
+            .animation
+            .as_ref()
+            .filter(|sprite_animation| !sprite_animation.paused && !sprite_animation.finished)
+            .and_then(|sprite_animation| engine_state.animations.get(&sprite_animation.name));
+        if let Some(animation) = animation {
+            if let Some(sprite_animation) = sprite.animation.as_mut() {
+                sprite_animation.advance(animation, delta);
+            }
+        }
+        let image = sprite_image(&sprite, &engine_state.animations);
+        if image != sprite.shown_image {
+            if let Some(material) = materials.get_mut(material_handle) {
+                material.texture =
+                    Some(image.texture(&asset_server, &mut textures, &mut shape_textures));
+            }
+            sprite.shown_image = image;
+        }
+    }
+
+    // Let go of the textures of shapes that no sprite shows anymore
+    let shown_shapes: Vec<_> = query
+        .iter()
+        .filter_map(|(sprite, _)| match &sprite.shown_image {
+            SpriteImage::Shape(shape) => Some(shape),
+            SpriteImage::File(_) => None,
+        })
+        .collect();
+    shape_textures.forget_unused(shown_shapes.into_iter());
+}
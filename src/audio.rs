@@ -250,3 +250,11 @@ pub fn queue_managed_audio_system(
     }
     game_state.audio_manager.playing = playing_music;
 }
+
+// The Bevy system used instead of `queue_managed_audio_system` when running headless. There is no
+// audio device to play anything on, so the queued audio is simply thrown away.
+#[doc(hidden)]
+pub fn discard_managed_audio_system(mut game_state: ResMut<EngineState>) {
+    game_state.audio_manager.sfx_queue.clear();
+    game_state.audio_manager.music_queue.clear();
+}
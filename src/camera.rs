@@ -0,0 +1,417 @@
+//! The camera decides which part of the game world is shown in the window.
+//!
+//! There is exactly one camera, and it lives in
+//! [`EngineState::camera`](crate::prelude::EngineState::camera). Move it by changing its
+//! `translation`, zoom in and out with `zoom`, and tilt the whole view with `rotation`. The camera
+//! can also follow a sprite around, stay inside the bounds of your level, and shake when something
+//! exciting happens.
+//!
+//! ```rust
+//! # use rusty_engine::prelude::*;
+//! # let mut camera = Camera::default();
+//! // Follow the player, but only once it moves 100 pixels away from the center of the screen
+//! camera.follow = Some("player".into());
+//! camera.follow_deadzone = Vec2::new(200.0, 200.0);
+//! // Never show anything outside of the level
+//! camera.bounds = Some((Vec2::new(-2000.0, -1000.0), Vec2::new(2000.0, 1000.0)));
+//! // Ouch!
+//! camera.shake(10.0, 0.5);
+//! ```
+//!
+//! Things like score displays and health bars shouldn't move around with the camera. Give a sprite
+//! or text a [`ScreenAnchor`] to put it on the UI layer, where it stays put on the screen no matter
+//! what the camera does.
+use crate::{
+    physics::rotate,
+    prelude::EngineState,
+    sprite::{Sprite, WorldTransform},
+    text::Text,
+};
+use bevy::{
+    prelude::*, render::camera::Camera as BevyCamera, transform::TransformSystem, utils::HashMap,
+};
+
+/// The smallest zoom the camera will actually use, to avoid dividing by zero
+const MIN_ZOOM: f32 = 0.001;
+/// How far the Bevy camera sits in front of everything, so that every layer is visible
+const BEVY_CAMERA_Z: f32 = 999.9;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(
+            update_camera
+                .system()
+                .label("update_camera")
+                .after("game_logic_sync"),
+        )
+        // New sprites and texts aren't spawned until the end of the update stage, so the UI layer
+        // is placed afterwards to catch them on their very first frame
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            sync_screen_anchors
+                .system()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// The camera that looks at the game world. Changes are shown the same frame they are made.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// SYNCED: The point in the world that is shown at the center of the window. While following a
+    /// sprite, the engine moves this for you.
+    pub translation: Vec2,
+    /// SYNCED: How magnified the view is. `2.0` makes everything twice as big, `0.5` shows twice as
+    /// much of the world. Must be greater than `0.0`. Defaults to `1.0`.
+    pub zoom: f32,
+    /// SYNCED: Rotation of the camera in radians. Rotating the camera counterclockwise makes the
+    /// world appear to rotate clockwise.
+    pub rotation: f32,
+    /// SYNCED: The label of a sprite for the camera to follow, if any
+    pub follow: Option<String>,
+    /// SYNCED: The size of a box at the center of the view that the followed sprite can move around
+    /// in without the camera moving. Defaults to `Vec2::ZERO`, which keeps the sprite centered.
+    pub follow_deadzone: Vec2,
+    /// SYNCED: How quickly the camera catches up to the followed sprite, from `0.0` (never) to
+    /// `1.0` (immediately). This is the fraction of the remaining distance covered every sixtieth
+    /// of a second, so the camera moves the same at any frame rate. Defaults to `0.1`.
+    pub follow_lerp: f32,
+    /// SYNCED: The bottom-left and top-right corners of the part of the world that the camera may
+    /// show, if any. The camera is kept far enough inside the bounds that nothing outside of them is
+    /// visible (ignoring `rotation`). If the view is bigger than the bounds, the bounds are
+    /// centered in the view.
+    pub bounds: Option<(Vec2, Vec2)>,
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_remaining: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            zoom: 1.0,
+            rotation: 0.0,
+            follow: None,
+            follow_deadzone: Vec2::ZERO,
+            follow_lerp: 0.1,
+            bounds: None,
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_remaining: 0.0,
+        }
+    }
+}
+
+impl Camera {
+    /// Shake the view by up to `intensity` pixels for `duration` seconds. The shaking fades out
+    /// over the duration. A new shake replaces any shake still going on. Shaking only moves what is
+    /// shown in the window--`translation` and mouse locations are unaffected.
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+        self.shake_intensity = intensity;
+        self.shake_duration = duration.max(0.0);
+        self.shake_remaining = self.shake_duration;
+    }
+
+    /// Returns true if the camera is still shaking
+    pub fn is_shaking(&self) -> bool {
+        self.shake_remaining > 0.0
+    }
+
+    /// Convert a point in screen space (in pixels, relative to the center of the window, with
+    /// positive y up) to the point in the world that the camera shows there
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        self.translation + rotate(point / self.zoom.max(MIN_ZOOM), self.rotation)
+    }
+
+    /// Convert a point in the world to where the camera shows it in screen space (in pixels,
+    /// relative to the center of the window, with positive y up)
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        rotate(point - self.translation, -self.rotation) * self.zoom.max(MIN_ZOOM)
+    }
+
+    /// How much of the world the camera shows in a window of the given size, ignoring `rotation`
+    pub fn visible_size(&self, window_dimensions: Vec2) -> Vec2 {
+        window_dimensions / self.zoom.max(MIN_ZOOM)
+    }
+
+    /// Where the Bevy camera goes, including any shaking
+    fn bevy_transform(&self) -> Transform {
+        let translation = self.translation + self.shake_offset();
+        let mut transform = Transform::from_xyz(translation.x, translation.y, BEVY_CAMERA_Z);
+        transform.rotation = Quat::from_rotation_z(self.rotation);
+        transform.scale = Vec3::splat(1.0 / self.zoom.max(MIN_ZOOM));
+        transform
+    }
+
+    /// Move toward the followed sprite's `target` location (if any) and keep within the bounds
+    fn update(&mut self, target: Option<Vec2>, window_dimensions: Vec2, delta: f32) {
+        if let Some(target) = target {
+            // Only the part of the offset that sticks out of the deadzone needs to be caught up on
+            let half_deadzone = self.follow_deadzone.abs() * 0.5;
+            let offset = target - self.translation;
+            let excess = offset - offset.clamp(-half_deadzone, half_deadzone);
+            let t = 1.0 - (1.0 - self.follow_lerp.clamp(0.0, 1.0)).powf(delta * 60.0);
+            self.translation += excess * t;
+        }
+        if let Some((min, max)) = self.bounds {
+            let half_visible = self.visible_size(window_dimensions) * 0.5;
+            self.translation.x = clamp_axis(self.translation.x, min.x, max.x, half_visible.x);
+            self.translation.y = clamp_axis(self.translation.y, min.y, max.y, half_visible.y);
+        }
+        self.shake_remaining = (self.shake_remaining - delta).max(0.0);
+    }
+
+    /// How far the shake moves the view right now
+    fn shake_offset(&self) -> Vec2 {
+        if !self.is_shaking() || self.shake_duration <= 0.0 {
+            return Vec2::ZERO;
+        }
+        // A few out-of-step sine waves look random enough, and keep headless runs deterministic
+        let falloff = self.shake_remaining / self.shake_duration;
+        let t = self.shake_duration - self.shake_remaining;
+        let x = ((t * 71.0).sin() + (t * 113.0).sin()) * 0.5;
+        let y = ((t * 89.0).sin() + (t * 131.0).cos()) * 0.5;
+        Vec2::new(x, y) * self.shake_intensity * falloff
+    }
+}
+
+/// A point on the edge (or in the center) of the screen that a sprite or text on the UI layer is
+/// placed relative to. See
+/// [`Sprite::screen_anchor`](crate::prelude::Sprite::screen_anchor) and
+/// [`Text::screen_anchor`](crate::prelude::Text::screen_anchor).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ScreenAnchor {
+    /// Where this anchor is in screen space (relative to the center of the window, with positive y
+    /// up) in a window of the given size. Compare with
+    /// [`MouseState::screen_location`](crate::prelude::MouseState::screen_location) to see if the
+    /// mouse is over something on the UI layer.
+    pub fn point(&self, window_dimensions: Vec2) -> Vec2 {
+        let half = window_dimensions * 0.5;
+        match self {
+            ScreenAnchor::TopLeft => Vec2::new(-half.x, half.y),
+            ScreenAnchor::Top => Vec2::new(0.0, half.y),
+            ScreenAnchor::TopRight => Vec2::new(half.x, half.y),
+            ScreenAnchor::Left => Vec2::new(-half.x, 0.0),
+            ScreenAnchor::Center => Vec2::ZERO,
+            ScreenAnchor::Right => Vec2::new(half.x, 0.0),
+            ScreenAnchor::BottomLeft => Vec2::new(-half.x, -half.y),
+            ScreenAnchor::Bottom => Vec2::new(0.0, -half.y),
+            ScreenAnchor::BottomRight => Vec2::new(half.x, -half.y),
+        }
+    }
+}
+
+/// Keep a view centered at `center` that extends `half_visible` in both directions between `min`
+/// and `max`
+fn clamp_axis(center: f32, min: f32, max: f32, half_visible: f32) -> f32 {
+    if max - min <= half_visible * 2.0 {
+        (min + max) * 0.5
+    } else {
+        center.clamp(min + half_visible, max - half_visible)
+    }
+}
+
+// system - move the camera and sync it to the Bevy camera
+fn update_camera(
+    mut engine_state: ResMut<EngineState>,
+    sprite_query: Query<&Sprite>,
+    mut camera_query: Query<&mut Transform, With<BevyCamera>>,
+) {
+    let engine_state = &mut *engine_state;
+    let target = engine_state.camera.follow.as_ref().and_then(|label| {
+        sprite_query
+            .iter()
+            .find(|sprite| &sprite.label == label)
+            .map(|sprite| sprite.world_translation)
+    });
+    engine_state.camera.update(
+        target,
+        engine_state.window_dimensions,
+        engine_state.delta_f32,
+    );
+
+    for mut transform in camera_query.iter_mut() {
+        *transform = engine_state.camera.bevy_transform();
+    }
+}
+
+/// Find the screen anchor that a sprite is placed relative to, which belongs to the sprite at the
+/// top of its family tree
+fn root_anchor(
+    label: &str,
+    sprites: &HashMap<String, (Option<String>, Option<ScreenAnchor>, WorldTransform)>,
+) -> Option<ScreenAnchor> {
+    let mut label = label;
+    // If we have gone through more parents than there are sprites, the parents form a loop
+    for _ in 0..=sprites.len() {
+        let (parent, screen_anchor, _) = sprites.get(label)?;
+        match parent {
+            Some(parent) if sprites.contains_key(parent) => label = parent,
+            _ => return *screen_anchor,
+        }
+    }
+    None
+}
+
+/// Place something in front of the camera, so that it shows up at `screen` in screen space
+fn in_front_of_camera(camera: &Transform, screen: Transform) -> Transform {
+    let mut transform = camera.mul_transform(screen);
+    // Keep the layer, rather than pushing the UI layer out past the far end of the camera
+    transform.translation.z = screen.translation.z;
+    transform
+}
+
+// system - place the sprites and texts on the UI layer in front of the camera
+fn sync_screen_anchors(
+    engine_state: Res<EngineState>,
+    mut query_set: QuerySet<(
+        Query<&Sprite>,
+        Query<(&Sprite, &mut Transform)>,
+        Query<(&Text, &mut Transform)>,
+    )>,
+) {
+    let sprites: HashMap<String, (Option<String>, Option<ScreenAnchor>, WorldTransform)> =
+        query_set
+            .q0()
+            .iter()
+            .map(|sprite| {
+                let info = (
+                    sprite.parent.clone(),
+                    sprite.screen_anchor,
+                    sprite.world_transform(),
+                );
+                (sprite.label.clone(), info)
+            })
+            .collect();
+    let camera = engine_state.camera.bevy_transform();
+    let window_dimensions = engine_state.window_dimensions;
+
+    for (sprite, mut transform) in query_set.q1_mut().iter_mut() {
+        if let Some(screen_anchor) = root_anchor(&sprite.label, &sprites) {
+            let mut screen = sprite.bevy_transform();
+            screen.translation += screen_anchor.point(window_dimensions).extend(0.0);
+            *transform = in_front_of_camera(&camera, screen);
+        }
+    }
+    for (text, mut transform) in query_set.q2_mut().iter_mut() {
+        // Attached texts are on the UI layer if their parent sprite is
+        let parent = text
+            .parent
+            .as_ref()
+            .filter(|label| sprites.contains_key(*label));
+        let screen_anchor = match parent {
+            Some(label) => root_anchor(label, &sprites),
+            None => text.screen_anchor,
+        };
+        if let Some(screen_anchor) = screen_anchor {
+            let parent = parent.map(|label| &sprites[label].2);
+            let mut screen = text.bevy_transform(parent);
+            screen.translation += screen_anchor.point(window_dimensions).extend(0.0);
+            *transform = in_front_of_camera(&camera, screen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).length() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn screen_and_world_conversions_round_trip() {
+        let camera = Camera {
+            translation: Vec2::new(100.0, -50.0),
+            zoom: 2.5,
+            rotation: 0.7,
+            ..Default::default()
+        };
+        for point in [Vec2::ZERO, Vec2::new(640.0, 360.0), Vec2::new(-123.4, 56.7)] {
+            assert_near(camera.world_to_screen(camera.screen_to_world(point)), point);
+            assert_near(camera.screen_to_world(camera.world_to_screen(point)), point);
+        }
+    }
+
+    #[test]
+    fn screen_to_world_pans_zooms_and_rotates() {
+        let mut camera = Camera::default();
+        assert_near(
+            camera.screen_to_world(Vec2::new(10.0, 20.0)),
+            Vec2::new(10.0, 20.0),
+        );
+        camera.translation = Vec2::new(100.0, 50.0);
+        camera.zoom = 2.0;
+        camera.rotation = FRAC_PI_2;
+        // Zoomed in, the edge of the screen is closer to the middle of the view. Rotated a quarter
+        // turn counterclockwise, the right side of the screen shows what is above the camera.
+        assert_near(
+            camera.screen_to_world(Vec2::new(10.0, 0.0)),
+            Vec2::new(100.0, 55.0),
+        );
+        assert_near(
+            camera.world_to_screen(Vec2::new(100.0, 55.0)),
+            Vec2::new(10.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn zero_zoom_does_not_divide_by_zero() {
+        let camera = Camera {
+            zoom: 0.0,
+            ..Default::default()
+        };
+        let world = camera.screen_to_world(Vec2::new(1.0, 1.0));
+        assert!(world.is_finite());
+        assert!(camera.visible_size(Vec2::new(800.0, 600.0)).is_finite());
+    }
+
+    #[test]
+    fn clamp_axis_keeps_the_view_inside_the_bounds() {
+        // Plenty of room
+        assert_eq!(clamp_axis(0.0, -100.0, 100.0, 25.0), 0.0);
+        // Too close to either edge
+        assert_eq!(clamp_axis(90.0, -100.0, 100.0, 25.0), 75.0);
+        assert_eq!(clamp_axis(-200.0, -100.0, 100.0, 25.0), -75.0);
+        // The bounds are exactly as big as the view, or smaller, so they are centered
+        assert_eq!(clamp_axis(40.0, -15.0, 35.0, 25.0), 10.0);
+        assert_eq!(clamp_axis(-40.0, -10.0, 30.0, 25.0), 10.0);
+    }
+
+    #[test]
+    fn bounds_keep_the_camera_inside_while_following() {
+        let mut camera = Camera {
+            follow_lerp: 1.0,
+            bounds: Some((Vec2::new(-500.0, -500.0), Vec2::new(500.0, 500.0))),
+            ..Default::default()
+        };
+        camera.update(
+            Some(Vec2::new(1000.0, 0.0)),
+            Vec2::new(400.0, 200.0),
+            1.0 / 60.0,
+        );
+        assert_near(camera.translation, Vec2::new(300.0, 0.0));
+    }
+}
@@ -1,20 +1,26 @@
 use bevy::prelude::{
-    info, AssetServer, Assets, Color, ColorMaterial, Commands, HorizontalAlign, Query, Res, ResMut,
-    SpriteBundle, Text as BevyText, Text2dBundle, TextAlignment, TextStyle, Vec2, VerticalAlign,
-    Windows,
+    info, AssetServer, Assets, Color, ColorMaterial, Commands, Query, Res, ResMut,
+    Sprite as BevySprite, SpriteBundle, Text2dBundle, Texture, Time, Vec2, Windows,
 };
 use bevy::utils::HashMap;
 pub use bevy::window::{WindowDescriptor, WindowMode, WindowResizeConstraints};
 use bevy_prototype_debug_lines::*;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use crate::{
+    animation::{self, Animation},
     audio::AudioManager,
+    camera::Camera,
+    input_script::InputScript,
     mouse::{CursorMoved, MouseButtonInput, MouseMotion, MouseWheel},
-    prelude::{CollisionEvent, KeyboardInput, KeyboardState, MouseState},
-    sprite::Sprite,
+    physics::{self, Collider, RaycastHit},
+    prelude::{CollisionEvent, CollisionPair, KeyboardInput, KeyboardState, MouseState},
+    shape::{ShapeTextures, SpriteShape},
+    sprite::{self, Sprite, WorldTransform},
     text::Text,
+    tilemap::Tilemap,
 };
 
 /// EngineState is the primary way that you will interact with Rusty Engine. Every frame this struct
@@ -38,17 +44,36 @@ pub struct EngineState {
     /// SYNCED - The state of all sprites this frame. To add a sprite, use the
     /// [`add_sprite`](EngineState::add_sprite) method. Modify & remove sprites as you like.
     pub sprites: HashMap<String, Sprite>,
+    /// SYNCED - All the animations that sprites can play, by name. To add an animation, use the
+    /// [`add_animation`](EngineState::add_animation) method.
+    pub animations: HashMap<String, Animation>,
     /// SYNCED - The state of all texts this frame. For convenience adding a text, use the
     /// [`add_text`](EngineState::add_text) method. Modify & remove text as you like.
     pub texts: HashMap<String, Text>,
+    /// SYNCED - All the tilemaps, by label. To add a tilemap, use the
+    /// [`add_tilemap`](EngineState::add_tilemap) method. Modify & remove tilemaps as you like.
+    pub tilemaps: HashMap<String, Tilemap>,
+    /// SYNCED - The [`Camera`] that decides which part of the world is shown in the window. Pan,
+    /// zoom, and rotate it, make it follow a sprite, or shake it.
+    pub camera: Camera,
     /// SYNCED - If set to `true`, the game exits. Note: the current frame will run to completion first.
     pub should_exit: bool,
-    /// SYNCED - If set to `true`, then debug lines are shown depicting sprite colliders
+    /// SYNCED - If set to `true`, then debug lines are shown depicting sprite colliders. Sensor
+    /// colliders are shown in yellow.
     pub debug_sprite_colliders: bool,
+    /// SYNCED - If set to `true`, then every frame that two sprites' colliders _continue_ to
+    /// overlap, a collision event with a
+    /// [`CollisionState::Ongoing`](crate::physics::CollisionState::Ongoing) state is generated.
+    /// Useful for things like applying damage every frame that a sprite is touching something.
+    /// Defaults to `false`.
+    pub ongoing_collision_events: bool,
     /// INFO - All the collision events that occurred this frame. For collisions to be generated
     /// between sprites, both sprites must have [`Sprite.collision`] set to `true`. Collision events
     /// are generated when two sprites' colliders begin or end overlapping in 2D space.
     pub collision_events: Vec<CollisionEvent>,
+    /// INFO - All the pairs of sprites whose colliders are currently overlapping. To find out what
+    /// a particular sprite is overlapping, see [`colliding_with`](EngineState::colliding_with).
+    pub current_collisions: HashSet<CollisionPair>,
     /// INFO - The current state of mouse location and buttons. Useful for input handling that only
     /// cares about the final state of the mouse each frame, and not the intermediate states.
     pub mouse_state: MouseState,
@@ -96,8 +121,8 @@ pub struct EngineState {
 impl EngineState {
     #[must_use]
     /// Add an [`Sprite`]. Use the `&mut Sprite` that is returned to set the translation, rotation,
-    /// etc. Use a unique label for each sprite. Attempting to add two sprites with the same label
-    /// will crash.
+    /// etc. Use a unique label for each sprite. Adding a sprite with a label that is already used
+    /// replaces the old sprite.
     pub fn add_sprite<T: Into<String>, P: Into<PathBuf>>(
         &mut self,
         label: T,
@@ -110,10 +135,41 @@ impl EngineState {
         self.sprites.get_mut(&label).unwrap()
     }
 
+    #[must_use]
+    /// Add a [`Sprite`] drawn as a [`SpriteShape`] instead of an image file. It gets a collider
+    /// that matches the shape. Use the `&mut Sprite` that is returned to set the translation,
+    /// rotation, etc. Use a unique label for each sprite. Adding a sprite with a label that is
+    /// already used replaces the old sprite.
+    pub fn add_shape<T: Into<String>>(&mut self, label: T, shape: SpriteShape) -> &mut Sprite {
+        let label = label.into();
+        self.sprites
+            .insert(label.clone(), Sprite::from_shape(label.clone(), shape));
+        // Unwrap: Can't crash because we just inserted the sprite
+        self.sprites.get_mut(&label).unwrap()
+    }
+
+    /// Add an [`Animation`] that sprites can play with
+    /// [`Sprite::play_animation`](crate::prelude::Sprite::play_animation). Any number of sprites may
+    /// play the same animation. Adding an animation with a label that is already used replaces the
+    /// old animation.
+    pub fn add_animation<T: Into<String>>(&mut self, label: T, animation: Animation) {
+        self.animations.insert(label.into(), animation);
+    }
+
+    #[must_use]
+    /// Add a [`Tilemap`]. Use the `&mut Tilemap` that is returned to set the translation, layer,
+    /// etc. Adding a tilemap with a label that is already used replaces the old tilemap.
+    pub fn add_tilemap(&mut self, tilemap: Tilemap) -> &mut Tilemap {
+        let label = tilemap.label.clone();
+        self.tilemaps.insert(label.clone(), tilemap);
+        // Unwrap: Can't crash because we just inserted the tilemap
+        self.tilemaps.get_mut(&label).unwrap()
+    }
+
     #[must_use]
     /// Add a [`Text`]. Use the `&mut Text` that is returned to set the translation, rotation, etc.
-    /// Use a unique label for each text. Attempting to add two texts with the same label will
-    /// crash.
+    /// Use a unique label for each text. Adding a text with a label that is already used replaces
+    /// the old text.
     pub fn add_text<T, S>(&mut self, label: T, text: S) -> &mut Text
     where
         T: Into<String>,
@@ -130,6 +186,100 @@ impl EngineState {
         // Unwrap: Can't crash because we just inserted the text
         self.texts.get_mut(&label).unwrap()
     }
+
+    /// Remove all the sprites and texts whose parent sprites were removed, along with their own
+    /// children, and so on. `previous_labels` are the labels of the sprites before your game logic
+    /// ran. A parent that isn't in `previous_labels` was never there to remove (it may be misspelled,
+    /// or not added yet), so sprites and texts attached to it are kept. The engine calls this for
+    /// you after your game logic runs.
+    #[doc(hidden)]
+    pub fn remove_orphans(&mut self, previous_labels: &HashSet<String>) {
+        let was_removed = |sprites: &HashMap<String, Sprite>, parent: &Option<String>| {
+            parent.as_ref().map_or(false, |parent| {
+                previous_labels.contains(parent) && !sprites.contains_key(parent)
+            })
+        };
+        loop {
+            let orphans: Vec<String> = self
+                .sprites
+                .values()
+                .filter(|sprite| was_removed(&self.sprites, &sprite.parent))
+                .map(|sprite| sprite.label.clone())
+                .collect();
+            if orphans.is_empty() {
+                break;
+            }
+            for label in orphans {
+                self.sprites.remove(&label);
+            }
+        }
+        let sprites = &self.sprites;
+        self.texts
+            .retain(|_, text| !was_removed(sprites, &text.parent));
+    }
+
+    /// The labels of all the sprites whose colliders are currently overlapping the collider of the
+    /// sprite with the given label. Only sprites with their `collision` fields set to `true` are
+    /// considered.
+    pub fn colliding_with<T: Into<String>>(&self, label: T) -> Vec<String> {
+        let label = label.into();
+        self.current_collisions
+            .iter()
+            .filter_map(|pair| {
+                if pair.0 == label {
+                    Some(pair.1.clone())
+                } else if pair.1 == label {
+                    Some(pair.0.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Cast a ray from `origin` in `direction` (which doesn't need to be unit length) and find the
+    /// first sprite collider it hits within `max_distance`. Sprites whose colliders contain `origin`
+    /// are skipped, so you can cast a ray from inside of a sprite (like a turret looking for a
+    /// target). Every sprite with a collider is considered, whether or not its `collision` field is
+    /// set.
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<RaycastHit> {
+        physics::raycast(self.sprites.values(), origin, direction, max_distance)
+    }
+
+    /// The labels of all the sprites whose colliders contain `point`. For example, pass in the
+    /// mouse location to find out which sprites are under the mouse. Every sprite with a collider is
+    /// considered, whether or not its `collision` field is set.
+    pub fn point_query(&self, point: Vec2) -> Vec<String> {
+        physics::point_query(self.sprites.values(), point)
+    }
+
+    /// The labels of all the sprites whose colliders overlap `collider`, if it were placed in the
+    /// world by `transform` and stretched by `stretch`, just like a sprite. `stretch` works like
+    /// [`Sprite::stretch`]: `Vec2::ONE` doesn't stretch the collider at all, and a negative value
+    /// flips it along that axis. To check a collider placed just like a sprite, pass in
+    /// [`sprite.world_transform()`](Sprite::world_transform). Every sprite with a collider is
+    /// considered, whether or not its `collision` field is set.
+    pub fn overlap_query(
+        &self,
+        collider: &Collider,
+        transform: WorldTransform,
+        stretch: Vec2,
+    ) -> Vec<String> {
+        physics::overlap_query(self.sprites.values(), collider, transform, stretch)
+    }
+}
+
+/// When present, the amount of time each frame is treated as taking, instead of the real time
+/// measured by Bevy. This is how headless runs advance time by a fixed, deterministic timestep.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeltaOverride(pub Option<Duration>);
+
+impl DeltaOverride {
+    /// The delta time to use for this frame
+    pub fn delta(&self, time: &Time) -> Duration {
+        self.0.unwrap_or_else(|| time.delta())
+    }
 }
 
 // startup system - grab window settings, initialize all the starting sprites
@@ -138,10 +288,25 @@ pub fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     materials: ResMut<Assets<ColorMaterial>>,
+    textures: ResMut<Assets<Texture>>,
+    mut shape_textures: ResMut<ShapeTextures>,
     mut engine_state: ResMut<EngineState>,
 ) {
-    add_sprites(&mut commands, &asset_server, materials, &mut engine_state);
-    add_texts(&mut commands, &asset_server, &mut engine_state);
+    let world_transforms = sprite::update_world_transforms(&mut engine_state.sprites);
+    add_sprites(
+        &mut commands,
+        &asset_server,
+        materials,
+        textures,
+        &mut shape_textures,
+        &mut engine_state,
+    );
+    add_texts(
+        &mut commands,
+        &asset_server,
+        &mut engine_state,
+        &world_transforms,
+    );
 }
 
 // helper function: Add Bevy components for all the sprites in engine_state.sprites
@@ -150,13 +315,27 @@ pub fn add_sprites(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+    shape_textures: &mut ShapeTextures,
     engine_state: &mut EngineState,
 ) {
-    for (_, sprite) in engine_state.sprites.drain() {
+    for (_, mut sprite) in engine_state.sprites.drain() {
+        sprite.refresh_collider();
         let transform = sprite.bevy_transform();
-        let texture_handle = asset_server.load(PathBuf::from("sprite").join(&sprite.filepath));
+        sprite.shown_image = animation::sprite_image(&sprite, &engine_state.animations);
+        let texture_handle =
+            sprite
+                .shown_image
+                .texture(asset_server, &mut textures, shape_textures);
+        let material = ColorMaterial::modulated_texture(texture_handle, sprite.material_color());
+        let bevy_sprite = BevySprite {
+            flip_x: sprite.flip_x,
+            flip_y: sprite.flip_y,
+            ..Default::default()
+        };
         commands.spawn().insert(sprite).insert_bundle(SpriteBundle {
-            material: materials.add(texture_handle.into()),
+            sprite: bevy_sprite,
+            material: materials.add(material),
             transform,
             ..Default::default()
         });
@@ -164,31 +343,24 @@ pub fn add_sprites(
 }
 
 /// Bevy system which adds any needed Bevy components to correspond to the texts in
-/// `engine_state.texts`
+/// `engine_state.texts`. `world_transforms` are the world transforms of the sprites, by label, for
+/// placing texts that are attached to sprites.
 #[doc(hidden)]
 pub fn add_texts(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     engine_state: &mut EngineState,
+    world_transforms: &HashMap<String, WorldTransform>,
 ) {
     for (_, text) in engine_state.texts.drain() {
-        let transform = text.bevy_transform();
-        let font_size = text.font_size;
-        let text_string = text.value.clone();
-        let font_path = format!("font/{}", text.font);
+        let parent = text
+            .parent
+            .as_ref()
+            .and_then(|label| world_transforms.get(label));
+        let transform = text.bevy_transform(parent);
+        let bevy_text = text.bevy_text(asset_server);
         commands.spawn().insert(text).insert_bundle(Text2dBundle {
-            text: BevyText::with_section(
-                text_string,
-                TextStyle {
-                    font: asset_server.load(font_path.as_str()),
-                    font_size,
-                    color: Color::WHITE,
-                },
-                TextAlignment {
-                    vertical: VerticalAlign::Center,
-                    horizontal: HorizontalAlign::Center,
-                },
-            ),
+            text: bevy_text,
             transform,
             ..Default::default()
         });
@@ -223,10 +395,20 @@ pub fn draw_sprite_colliders(
         if length < 2 {
             continue;
         }
+        let color = if sprite.sensor {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
         let mut curr = 0;
         let mut next = 1;
         while curr < length {
-            lines.line(points[curr].extend(0.0), points[next].extend(0.0), 0.0);
+            lines.line_colored(
+                points[curr].extend(0.0),
+                points[next].extend(0.0),
+                0.0,
+                color,
+            );
             curr += 1;
             next = (next + 1) % length;
         }
@@ -266,6 +448,40 @@ impl Game {
         panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
     }
 
+    /// Feed a scripted timeline of keyboard and mouse input into the game as it runs. See
+    /// [`InputScript`](crate::input_script::InputScript) for details.
+    ///
+    /// *Note:* YOU NEED TO USE THE VERSION OF `Game` GENERATED BY THE `rusty_engine::init!( ... )`
+    /// MACRO CALL!  _This_ version is a dummy just so we can document how to use the generated version!
+    #[allow(unused_variables)]
+    pub fn input_script(&mut self, input_script: InputScript) -> &mut Self {
+        panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
+    }
+
+    /// Record all keyboard and mouse input, along with the timing of every frame, to a file as the
+    /// game runs. `filepath` is relative to the current directory (usually the root of your
+    /// project). The recording can be played back with [`replay_input`](Game::replay_input). See
+    /// the [`recording`](crate::recording) module for more details.
+    ///
+    /// *Note:* YOU NEED TO USE THE VERSION OF `Game` GENERATED BY THE `rusty_engine::init!( ... )`
+    /// MACRO CALL!  _This_ version is a dummy just so we can document how to use the generated version!
+    #[allow(unused_variables)]
+    pub fn record_input<P: AsRef<Path>>(&mut self, filepath: P) -> &mut Self {
+        panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
+    }
+
+    /// Replay input recorded with [`record_input`](Game::record_input). The recorded input is fed
+    /// to the game on exactly the same frames, with exactly the same frame timings, as when it was
+    /// recorded. `filepath` is relative to the current directory (usually the root of your
+    /// project).
+    ///
+    /// *Note:* YOU NEED TO USE THE VERSION OF `Game` GENERATED BY THE `rusty_engine::init!( ... )`
+    /// MACRO CALL!  _This_ version is a dummy just so we can document how to use the generated version!
+    #[allow(unused_variables)]
+    pub fn replay_input<P: AsRef<Path>>(&mut self, filepath: P) -> &mut Self {
+        panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
+    }
+
     /// Start the game.
     ///
     /// # Examples
@@ -278,6 +494,29 @@ impl Game {
         panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
     }
 
+    /// Run the game without a window, audio, or a GPU, which is useful for automated tests of your
+    /// game logic (for example, in CI). Instead of measuring real time, each frame advances time by
+    /// exactly `timestep`, so runs are deterministic. The game runs for `frames` frames, or until
+    /// [`EngineState::should_exit`] is set to `true`, whichever comes first.
+    ///
+    /// Your final game state is returned. Afterwards, the final [`EngineState`] is available
+    /// through the `Game` itself, so you can assert on things like `game.sprites`.
+    ///
+    /// The type of `initial_game_state` (and of the game state that is returned) is the type you
+    /// pass into `rusty_engine::init!()`, or a unit struct `()` if you don't pass anything in.
+    ///
+    /// *Note:* YOU NEED TO USE THE VERSION OF `Game` GENERATED BY THE `rusty_engine::init!( ... )`
+    /// MACRO CALL!  _This_ version is a dummy just so we can document how to use the generated version!
+    #[allow(unused_variables)]
+    pub fn run_headless<S>(
+        &mut self,
+        initial_game_state: S,
+        frames: usize,
+        timestep: Duration,
+    ) -> S {
+        panic!("Use the `Game` struct generated by rusty_engine::init!( ... ). See https://github.com/CleanCut/rusty_engine/#quick-start")
+    }
+
     #[allow(unused_variables)]
     /// `logic_function` is a function or closure that takes two parameters:
     ///
@@ -300,21 +539,26 @@ macro_rules! init {
 
 use rusty_engine::{
     audio::AudioManager,
+    input_script::{InputScript, InputScriptPlugin},
+    recording::{InputRecorder, InputRecordingPlugin, InputReplay},
     mouse::{CursorMoved, MouseButtonInput, MouseMotion, MousePlugin, MouseWheel},
     prelude::{
-        AudioManagerPlugin, CollisionEvent, KeyboardInput, KeyboardPlugin, KeyboardState,
-        MouseState, PhysicsPlugin,
+        AnimationPlugin, AudioManagerPlugin, CollisionEvent, KeyboardInput, KeyboardPlugin, KeyboardState,
+        CameraPlugin, MouseState, PhysicsPlugin, TilemapPlugin,
     },
-    game::{draw_sprite_colliders, update_window_dimensions},
+    game::{draw_sprite_colliders, update_window_dimensions, DeltaOverride},
+    shape::ShapeTextures,
     sprite::{Sprite, SpritePreset},
-    text::Text,
+    text::{bevy_text_eq, Text, TextPlugin},
 };
 use bevy::{app::AppExit, input::system::exit_on_esc_system,
+    render::renderer::{HeadlessRenderResourceContext, RenderResourceContext},
+    window::{Window, WindowId, Windows},
     prelude::{
         App, AppBuilder, Assets, AssetServer, Color, ColorMaterial, Commands, DefaultPlugins,
-        Entity, EventReader, EventWriter, IntoSystem, OrthographicCameraBundle,
-        ParallelSystemDescriptorCoercion, Query, QuerySet, Res, ResMut, Text as BevyText, Transform,
-        Vec3,
+        Entity, EventReader, EventWriter, Handle, IntoSystem, OrthographicCameraBundle,
+        ParallelSystemDescriptorCoercion, Query, QuerySet, Res, ResMut, Sprite as BevySprite,
+        Text as BevyText, Texture, Transform, Vec3,
     }, utils::HashMap};
 use bevy_kira_audio::*;
 use bevy_prototype_debug_lines::*;
@@ -332,6 +576,9 @@ struct Game {
     engine_state: EngineState,
     logic_functions: Vec<LogicFunction>,
     window_descriptor: WindowDescriptor,
+    input_script: InputScript,
+    input_recorder: InputRecorder,
+    input_replay: InputReplay,
 }
 
 impl Default for Game {
@@ -344,6 +591,9 @@ impl Default for Game {
                 title: "Rusty Engine".into(),
                 ..Default::default()
             },
+            input_script: InputScript::default(),
+            input_recorder: InputRecorder::default(),
+            input_replay: InputReplay::default(),
         }
     }
 }
@@ -365,15 +615,39 @@ impl Game {
         self
     }
 
+    /// documented in the public stub
+    fn input_script(&mut self, input_script: InputScript) -> &mut Self {
+        self.input_script = input_script;
+        self
+    }
+
+    /// documented in the public stub
+    fn record_input<P: AsRef<std::path::Path>>(&mut self, filepath: P) -> &mut Self {
+        self.input_recorder = InputRecorder::new(filepath);
+        self
+    }
+
+    /// documented in the public stub
+    fn replay_input<P: AsRef<std::path::Path>>(&mut self, filepath: P) -> &mut Self {
+        self.input_replay = InputReplay::from_file(filepath);
+        self
+    }
+
     /// documented in the public stub
     fn run(&mut self, initial_game_state: $game_state_type) {
         self.app_builder
             .insert_resource::<WindowDescriptor>(self.window_descriptor.clone())
-            .insert_resource::<$game_state_type>(initial_game_state);
+            .insert_resource::<$game_state_type>(initial_game_state)
+            .insert_resource(DeltaOverride::default())
+            .insert_resource(std::mem::take(&mut self.input_script))
+            .insert_resource(std::mem::take(&mut self.input_recorder))
+            .insert_resource(std::mem::take(&mut self.input_replay));
         self.app_builder
             // Built-ins
             .add_plugins_with(DefaultPlugins, |group| {
-                group.disable::<bevy::audio::AudioPlugin>()
+                group
+                    .disable::<bevy::audio::AudioPlugin>()
+                    .disable::<bevy::text::TextPlugin>() // replaced by our TextPlugin
             })
             .add_system(exit_on_esc_system.system())
             // External Plugins
@@ -381,9 +655,15 @@ impl Game {
             .add_plugin(DebugLinesPlugin) // bevy_prototype_debug_lines, for debugging sprite colliders
             // Rusty Engine Plugins
             .add_plugin(AudioManagerPlugin)
+            .add_plugin(InputRecordingPlugin)
+            .add_plugin(InputScriptPlugin)
             .add_plugin(KeyboardPlugin)
             .add_plugin(MousePlugin)
             .add_plugin(PhysicsPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(TilemapPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(TextPlugin)
             //.insert_resource(ReportExecutionOrderAmbiguities) // for debugging
             .add_system(update_window_dimensions.system().label("update_window_dimensions").before("game_logic_sync"))
             .add_system(game_logic_sync.system().label("game_logic_sync"))
@@ -400,6 +680,100 @@ impl Game {
         self.app_builder.run();
     }
 
+    /// documented in the public stub
+    fn run_headless(
+        &mut self,
+        initial_game_state: $game_state_type,
+        frames: usize,
+        timestep: Duration,
+    ) -> $game_state_type {
+        // There is no real window, so we add a stand-in primary window that is exactly the size
+        // that was asked for. Systems that need the primary window will find this one.
+        let mut windows = Windows::default();
+        windows.add(Window::new(
+            WindowId::primary(),
+            &self.window_descriptor,
+            self.window_descriptor.width as u32,
+            self.window_descriptor.height as u32,
+            1.0,
+            None,
+        ));
+        self.app_builder
+            .insert_resource::<WindowDescriptor>(self.window_descriptor.clone())
+            .insert_resource::<$game_state_type>(initial_game_state)
+            .insert_resource(DeltaOverride(Some(timestep)))
+            .insert_resource(std::mem::take(&mut self.input_script))
+            .insert_resource(std::mem::take(&mut self.input_recorder))
+            .insert_resource(std::mem::take(&mut self.input_replay))
+            .insert_resource(windows)
+            .insert_resource::<Box<dyn RenderResourceContext>>(Box::new(
+                HeadlessRenderResourceContext::default(),
+            ));
+        self.app_builder
+            // Built-ins, minus everything that needs a window, a GPU, or an audio device. Logging
+            // can only be set up once per process, so it is left off to allow more than one
+            // headless run (say, one per test).
+            .add_plugins_with(DefaultPlugins, |group| {
+                group
+                    .disable::<bevy::log::LogPlugin>()
+                    .disable::<bevy::audio::AudioPlugin>()
+                    .disable::<bevy::gilrs::GilrsPlugin>()
+                    .disable::<bevy::winit::WinitPlugin>()
+                    .disable::<bevy::wgpu::WgpuPlugin>()
+                    .disable::<bevy::text::TextPlugin>() // replaced by our TextPlugin
+            })
+            // Rusty Engine Plugins
+            .add_plugin(InputRecordingPlugin)
+            .add_plugin(InputScriptPlugin)
+            .add_plugin(KeyboardPlugin)
+            .add_plugin(MousePlugin)
+            .add_plugin(PhysicsPlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(TilemapPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(TextPlugin)
+            .add_system(rusty_engine::audio::discard_managed_audio_system.system())
+            .add_system(update_window_dimensions.system().label("update_window_dimensions").before("game_logic_sync"))
+            .add_system(game_logic_sync.system().label("game_logic_sync"))
+            .add_startup_system(rusty_engine::game::setup.system());
+        let world = self.app_builder.world_mut();
+        world
+            .spawn()
+            .insert_bundle(OrthographicCameraBundle::new_2d());
+        let engine_state = std::mem::take(&mut self.engine_state);
+        self.app_builder.insert_resource(engine_state);
+        let logic_functions = std::mem::take(&mut self.logic_functions);
+        self.app_builder.insert_resource(logic_functions);
+
+        // Drive the frames ourselves instead of handing control over to a runner
+        let mut app = std::mem::take(&mut self.app_builder.app);
+        for _ in 0..frames {
+            app.update();
+            // Unwrap: We inserted the EngineState resource above
+            if app.world.get_resource::<EngineState>().unwrap().should_exit {
+                break;
+            }
+        }
+
+        // Gather up the final state of the sprites and texts so they can be inspected
+        let world = &mut app.world;
+        // Unwraps: We inserted both of these resources above
+        let mut engine_state = world.remove_resource::<EngineState>().unwrap();
+        let game_state = world.remove_resource::<$game_state_type>().unwrap();
+        engine_state.sprites = world
+            .query::<&Sprite>()
+            .iter(world)
+            .map(|sprite| (sprite.label.clone(), sprite.clone()))
+            .collect();
+        engine_state.texts = world
+            .query::<&Text>()
+            .iter(world)
+            .map(|text| (text.label.clone(), text.clone()))
+            .collect();
+        self.engine_state = engine_state;
+        game_state
+    }
+
     /// documented in the public stub
     fn add_logic(&mut self, logic_function: LogicFunction) {
         self.logic_functions.push(logic_function);
@@ -411,36 +785,42 @@ impl Game {
 fn game_logic_sync(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    textures: ResMut<Assets<Texture>>,
+    mut shape_textures: ResMut<ShapeTextures>,
     mut engine_state: ResMut<EngineState>,
     mut game_state: ResMut<$game_state_type>,
     logic_functions: Res<Vec<LogicFunction>>,
     keyboard_state: Res<KeyboardState>,
     mouse_state: Res<MouseState>,
     time: Res<Time>,
+    delta_override: Res<DeltaOverride>,
     mut app_exit_events: EventWriter<AppExit>,
     mut collision_events: EventReader<CollisionEvent>,
     mut query_set: QuerySet<(
         Query<&Sprite>,
         Query<&Text>,
-        Query<(Entity, &mut Sprite, &mut Transform)>,
+        Query<(Entity, &mut Sprite, &mut Transform, &mut BevySprite, &Handle<ColorMaterial>)>,
         Query<(Entity, &mut Text, &mut Transform, &mut BevyText)>,
     )>,
 ) {
     // Update this frame's timing info
-    engine_state.delta = time.delta();
-    engine_state.delta_f32 = time.delta_seconds();
-    engine_state.time_since_startup = time.time_since_startup();
-    engine_state.time_since_startup_f64 = time.seconds_since_startup();
-
-    // TODO: Transfer any changes to the Bevy components by the physics system over to the Sprites
-    // for (mut sprite, mut transform) in sprite_query.iter_mut() {
-    //     sprite.translation = Vec2::from(transform.translation);
-    //     sprite.layer = transform.translation.z;
-    //     // transform.rotation = Quat::from_axis_angle(Vec3::Z, sprite.rotation);
-    //     sprite.rotation = ???
-    //     sprite.scale = transform.scale.x;
-    // }
+    if delta_override.0.is_some() {
+        // Time is advancing by a fixed amount each frame, so startup time is the sum of the deltas
+        let delta = delta_override.delta(&time);
+        engine_state.delta = delta;
+        engine_state.delta_f32 = delta.as_secs_f32();
+        engine_state.time_since_startup += delta;
+        engine_state.time_since_startup_f64 = engine_state.time_since_startup.as_secs_f64();
+    } else {
+        engine_state.delta = time.delta();
+        engine_state.delta_f32 = time.delta_seconds();
+        engine_state.time_since_startup = time.time_since_startup();
+        engine_state.time_since_startup_f64 = time.seconds_since_startup();
+    }
+
+    // The physics systems have already run this frame, and they work directly on the Sprite
+    // components, so any movement they did is picked up when we copy the sprites below
 
     // Copy keyboard state over to engine_state to give to users
     engine_state.keyboard_state = keyboard_state.clone();
@@ -471,6 +851,7 @@ fn game_logic_sync(
     }
 
     // Perform all the user's game logic for this frame
+    let previous_labels: std::collections::HashSet<String> = engine_state.sprites.keys().cloned().collect();
     for func in logic_functions.iter() {
         // If the user returns false, abort the rest of the game logic
         if !func(&mut engine_state, &mut game_state) {
@@ -478,11 +859,31 @@ fn game_logic_sync(
         }
     }
 
-    // Transfer any changes in the user's Sprite copies to the Bevy Sprite and Transform components
-    for (entity, mut sprite, mut transform) in query_set.q2_mut().iter_mut() {
-        if let Some(sprite_copy) = engine_state.sprites.remove(&sprite.label) {
+    // Remove sprites and texts whose parents were removed, then work out where everything that
+    // is left is in world space
+    engine_state.remove_orphans(&previous_labels);
+    let world_transforms = rusty_engine::sprite::update_world_transforms(&mut engine_state.sprites);
+
+    // Transfer any changes in the user's Sprite copies to the Bevy Sprite, Transform, and
+    // ColorMaterial components
+    for (entity, mut sprite, mut transform, mut bevy_sprite, material_handle) in
+        query_set.q2_mut().iter_mut()
+    {
+        if let Some(mut sprite_copy) = engine_state.sprites.remove(&sprite.label) {
+            // If the sprite has a new image, it needs the collider that goes with it. The image
+            // itself is swapped by the animation plugin.
+            sprite_copy.refresh_collider();
             *sprite = sprite_copy;
             *transform = sprite.bevy_transform();
+            if bevy_sprite.flip_x != sprite.flip_x || bevy_sprite.flip_y != sprite.flip_y {
+                bevy_sprite.flip_x = sprite.flip_x;
+                bevy_sprite.flip_y = sprite.flip_y;
+            }
+            let color = sprite.material_color();
+            if materials.get(material_handle).map_or(false, |material| material.color != color) {
+                // Unwrap: We just found the material
+                materials.get_mut(material_handle).unwrap().color = color;
+            }
         } else {
             commands.entity(entity).despawn();
         }
@@ -491,19 +892,18 @@ fn game_logic_sync(
     // Transfer any changes in the user's Texts to the Bevy Text and Transform components
     for (entity, mut text, mut transform, mut bevy_text_component) in query_set.q3_mut().iter_mut() {
         if let Some(text_copy) = engine_state.texts.remove(&text.label) {
+            // The text only gets laid out again when the Bevy Text changes, so new bounds need to
+            // count as a change too
+            let bounds_changed = text.max_width != text_copy.max_width || text.max_height != text_copy.max_height;
+            // The size is only ever set by laying the text out
+            let size = text.size;
             *text = text_copy;
-            *transform = text.bevy_transform();
-            if text.value != bevy_text_component.sections[0].value {
-                bevy_text_component.sections[0].value = text.value.clone();
-            }
-            #[allow(clippy::float_cmp)]
-            if text.font_size != bevy_text_component.sections[0].style.font_size {
-                bevy_text_component.sections[0].style.font_size = text.font_size;
-            }
-            let font_path = format!("font/{}", text.font);
-            let font = asset_server.load(font_path.as_str());
-            if bevy_text_component.sections[0].style.font != font {
-                bevy_text_component.sections[0].style.font = font;
+            text.size = size;
+            let parent = text.parent.as_ref().and_then(|label| world_transforms.get(label));
+            *transform = text.bevy_transform(parent);
+            let bevy_text = text.bevy_text(&asset_server);
+            if bounds_changed || !bevy_text_eq(&bevy_text_component, &bevy_text) {
+                *bevy_text_component = bevy_text;
             }
         } else {
             commands.entity(entity).despawn();
@@ -511,10 +911,10 @@ fn game_logic_sync(
     }
 
     // Add Bevy components for any new sprites remaining in engine_state.sprites
-    rusty_engine::game::add_sprites(&mut commands, &asset_server, materials, &mut engine_state);
+    rusty_engine::game::add_sprites(&mut commands, &asset_server, materials, textures, &mut shape_textures, &mut engine_state);
 
     // Add Bevy components for any new texts remaining in engine_state.texts
-    rusty_engine::game::add_texts(&mut commands, &asset_server, &mut engine_state);
+    rusty_engine::game::add_texts(&mut commands, &asset_server, &mut engine_state, &world_transforms);
 
     if engine_state.should_exit {
         app_exit_events.send(AppExit);
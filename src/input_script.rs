@@ -0,0 +1,203 @@
+//! Facilities for feeding scripted keyboard and mouse input into a running game. This is mostly
+//! useful for testing your game logic frame by frame without a human at the keyboard, usually
+//! together with [`Game::run_headless`](crate::game::Game::run_headless).
+//!
+//! Scripted input goes through exactly the same path as real input, so
+//! [`KeyboardState`](crate::prelude::KeyboardState),
+//! [`MouseState`](crate::prelude::MouseState), and the input event fields on
+//! [`EngineState`](crate::prelude::EngineState) all behave as if the input came from real hardware.
+//!
+//! Input scripts can be built in code, or loaded from a
+//! [Rusty Object Notation (RON)](https://github.com/ron-rs/ron) file that looks like this (mouse
+//! locations are in world space, just like sprite translations):
+//!
+//! ```text
+//! (
+//!     events: [
+//!         (frame: 0, action: KeyPress(Up)),
+//!         (frame: 30, action: KeyRelease(Up)),
+//!         (frame: 31, action: MouseLocation((100.0, -50.0))),
+//!         (frame: 31, action: MousePress(Left)),
+//!         (frame: 32, action: MouseRelease(Left)),
+//!         (frame: 40, action: MouseWheel((0.0, 1.0))),
+//!     ],
+//! )
+//! ```
+use crate::{
+    game::EngineState,
+    keyboard::{KeyCode, KeyboardInput},
+    mouse::{CursorMoved, ElementState, MouseButton, MouseButtonInput, MouseMotion, MouseWheel},
+};
+use bevy::{input::mouse::MouseScrollUnit, prelude::*, window::WindowId};
+use serde::{Deserialize, Serialize};
+use std::{fs::File, path::Path};
+
+pub struct InputScriptPlugin;
+
+impl Plugin for InputScriptPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<InputScript>()
+            // Scripted input needs to be sent before Bevy processes input in `PreUpdate`
+            .add_system_to_stage(
+                CoreStage::First,
+                play_input_script.system().label("play_input_script"),
+            );
+    }
+}
+
+/// A single action of an [`InputScript`]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum InputAction {
+    /// Start pressing a key
+    KeyPress(KeyCode),
+    /// Stop pressing a key
+    KeyRelease(KeyCode),
+    /// Start pressing a mouse button
+    MousePress(MouseButton),
+    /// Stop pressing a mouse button
+    MouseRelease(MouseButton),
+    /// Move the mouse to a location in world space--the same coordinates that sprites use. The
+    /// location is converted to a point on the screen through
+    /// [`EngineState::camera`](crate::prelude::EngineState::camera) as it was at the end of the
+    /// previous frame, so [`MouseState::location`](crate::prelude::MouseState::location) reports
+    /// exactly this location, wherever the camera is.
+    MouseLocation(Vec2),
+    /// Scroll the mouse wheel by some amount of lines. The y component is the "normal" scrolling
+    /// direction of a mouse wheel.
+    MouseWheel(Vec2),
+}
+
+/// An [`InputAction`] along with the frame it should happen on
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ScriptedInput {
+    /// The frame the action happens on. The first frame of the game is frame `0`.
+    pub frame: u64,
+    /// What happens
+    pub action: InputAction,
+}
+
+/// A timeline of keyboard and mouse input to feed into a game. Pass it to `Game::input_script`
+/// before running the game.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct InputScript {
+    /// All the scripted input, in the order it will happen
+    pub events: Vec<ScriptedInput>,
+    #[doc(hidden)]
+    #[serde(skip)]
+    // How far into the events we have gotten while playing the script
+    next_event: usize,
+}
+
+impl InputScript {
+    /// Create an empty [`InputScript`]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Load an [`InputScript`] from a RON file. `filepath` is relative to the current directory
+    /// (usually the root of your project), not the `assets/` directory. If the file can't be read
+    /// or parsed, an error is printed and `None` is returned.
+    pub fn from_file<P: AsRef<Path>>(filepath: P) -> Option<Self> {
+        let fh = match File::open(filepath.as_ref()) {
+            Ok(fh) => fh,
+            Err(e) => {
+                eprintln!("failed to open input script file: {}", e);
+                return None;
+            }
+        };
+        match ron::de::from_reader::<_, InputScript>(fh) {
+            Ok(mut input_script) => {
+                input_script.sort();
+                Some(input_script)
+            }
+            Err(e) => {
+                eprintln!("failed deserializing input script from file: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Add an [`InputAction`] to happen on `frame`. Returns the script, so you can chain calls.
+    pub fn add(&mut self, frame: u64, action: InputAction) -> &mut Self {
+        self.events.push(ScriptedInput { frame, action });
+        self.sort();
+        self
+    }
+
+    // Keep the events in frame order. The sort is stable, so actions on the same frame keep the
+    // order they were added in.
+    fn sort(&mut self) {
+        self.events
+            .sort_by_key(|scripted_input| scripted_input.frame);
+    }
+}
+
+// system - send any scripted input for this frame as if it came from real hardware
+#[allow(clippy::too_many_arguments)]
+fn play_input_script(
+    mut frame: Local<u64>,
+    mut last_location: Local<Option<Vec2>>,
+    mut input_script: ResMut<InputScript>,
+    engine_state: Res<EngineState>,
+    windows: Res<Windows>,
+    mut keyboard_input_events: EventWriter<KeyboardInput>,
+    mut mouse_button_events: EventWriter<MouseButtonInput>,
+    mut cursor_moved_events: EventWriter<CursorMoved>,
+    mut mouse_motion_events: EventWriter<MouseMotion>,
+    mut mouse_wheel_events: EventWriter<MouseWheel>,
+) {
+    let this_frame = *frame;
+    *frame += 1;
+    while let Some(scripted_input) = input_script.events.get(input_script.next_event).copied() {
+        if scripted_input.frame > this_frame {
+            break;
+        }
+        input_script.next_event += 1;
+        match scripted_input.action {
+            InputAction::KeyPress(key_code) => keyboard_input_events.send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key_code),
+                state: ElementState::Pressed,
+            }),
+            InputAction::KeyRelease(key_code) => keyboard_input_events.send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key_code),
+                state: ElementState::Released,
+            }),
+            InputAction::MousePress(button) => mouse_button_events.send(MouseButtonInput {
+                button,
+                state: ElementState::Pressed,
+            }),
+            InputAction::MouseRelease(button) => mouse_button_events.send(MouseButtonInput {
+                button,
+                state: ElementState::Released,
+            }),
+            InputAction::MouseLocation(location) => {
+                // Convert from world space through the camera to the screen, and then from our
+                // screen space to Bevy's window space
+                let location = engine_state.camera.world_to_screen(location);
+                let window_dimensions = windows
+                    .get_primary()
+                    .map(|window| Vec2::new(window.width(), window.height()))
+                    .unwrap_or_default();
+                cursor_moved_events.send(CursorMoved {
+                    id: WindowId::primary(),
+                    position: location + window_dimensions * 0.5,
+                });
+                // Real mice report relative motion along with the new location. Motion is in
+                // window space, where positive y is down.
+                if let Some(last) = *last_location {
+                    let mut delta = location - last;
+                    delta.y *= -1.0;
+                    mouse_motion_events.send(MouseMotion { delta });
+                }
+                *last_location = Some(location);
+            }
+            InputAction::MouseWheel(amount) => mouse_wheel_events.send(MouseWheel {
+                unit: MouseScrollUnit::Line,
+                x: amount.x,
+                y: amount.y,
+            }),
+        }
+    }
+}
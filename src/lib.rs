@@ -72,17 +72,26 @@
 //! [this `README.md` file](https://github.com/CleanCut/rusty_engine/tree/main/assets/audio/music)
 //! for more information.
 //!
+pub mod animation;
 pub mod audio;
+pub mod camera;
 pub mod game;
+pub mod input_script;
 pub mod keyboard;
 pub mod mouse;
 pub mod physics;
+pub mod recording;
+pub mod shape;
 pub mod sprite;
 pub mod text;
+pub mod tilemap;
 
 // Public prelude
 pub mod prelude {
-    pub use crate::{audio::*, keyboard::*, mouse::*, physics::*, sprite::*, text::*};
+    pub use crate::{
+        animation::*, audio::*, camera::*, input_script::*, keyboard::*, mouse::*, physics::*,
+        shape::*, sprite::*, text::*, tilemap::*,
+    };
     pub use crate::{
         DOWN, EAST, LEFT, NORTH, NORTH_EAST, NORTH_WEST, RIGHT, SOUTH, SOUTH_EAST, SOUTH_WEST, UP,
         WEST,
@@ -91,7 +100,7 @@ pub mod prelude {
     pub use crate::game::{EngineState, WindowDescriptor, WindowMode, WindowResizeConstraints};
     pub use bevy::{
         self,
-        prelude::{Time, Timer, Vec2},
+        prelude::{Color, Time, Timer, Vec2},
     };
 }
 
@@ -30,6 +30,7 @@ impl Plugin for MousePlugin {
 #[derive(Clone, Debug, Default)]
 pub struct MouseState {
     location: Option<Vec2>,
+    screen_location: Option<Vec2>,
     motion: Vec2,
     wheel: MouseWheelState,
     pressed: HashSet<MouseButton>,
@@ -56,13 +57,19 @@ pub struct MouseWheelState {
 }
 
 impl MouseState {
-    /// Final location of the mouse this frame. If you want to process _all_ the locations the mouse
-    /// was at during this frame, see
+    /// Final location of the mouse this frame, in world space. That is, the point in the world
+    /// under the mouse, as seen through [`EngineState::camera`](crate::prelude::EngineState::camera).
+    /// If you want to process _all_ the locations the mouse was at during this frame, see
     /// [`EngineState::mouse_location_events`](crate::prelude::EngineState::mouse_location_events)
     /// instead.
     pub fn location(&self) -> Option<Vec2> {
         self.location
     }
+    /// Final location of the mouse this frame, in screen space (relative to the center of the
+    /// window, with positive y up), no matter where the camera is
+    pub fn screen_location(&self) -> Option<Vec2> {
+        self.screen_location
+    }
     /// The cumulative relative motion of the mouse this frame. If you want to process _all_ the
     /// individual relative motions, see
     /// [`EngineState::mouse_motion_events](crate::prelude::EngineState::mouse_motion_events) instead.
@@ -120,9 +127,10 @@ fn sync_mouse_events(
     }
     for ev in cursor_moved_events.iter() {
         let mut new_event = ev.clone();
-        // Convert from screen space to game space
+        // Convert from screen space to game space, then through the camera to world space
         // TODO: Check to see if this needs to be adjusted for different DPIs
-        new_event.position -= game_state.window_dimensions * 0.5;
+        let screen_position = new_event.position - game_state.window_dimensions * 0.5;
+        new_event.position = game_state.camera.screen_to_world(screen_position);
         game_state.mouse_location_events.push(new_event);
     }
     for ev in mouse_motion_events.iter() {
@@ -148,8 +156,12 @@ fn sync_mouse_state(
     if let Some(event) = cursor_moved_events.iter().last() {
         // Convert from bevy's window space to our game space
         let location = event.position - game_state.window_dimensions * 0.5;
-        mouse_state.location = Some(location);
+        mouse_state.screen_location = Some(location);
     }
+    // The camera may have moved even if the mouse didn't, so always convert to world space
+    mouse_state.location = mouse_state
+        .screen_location
+        .map(|location| game_state.camera.screen_to_world(location));
     // Sync the relative mouse motion. This is the cumulative relative motion during the last frame.
     mouse_state.motion = Vec2::ZERO;
     for ev in mouse_motion_events.iter() {
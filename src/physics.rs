@@ -1,18 +1,46 @@
-use crate::sprite::Sprite;
+use crate::{
+    game::DeltaOverride,
+    prelude::EngineState,
+    sprite::{self, Sprite, WorldTransform},
+};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     f32::consts::{PI, TAU},
     hash::Hash,
+    sync::Arc,
 };
 
+// The most times a dynamic sprite is pushed out of a tilemap's solid tiles in a single frame
+const MAX_TILE_PUSHES: usize = 4;
+
 pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_event::<CollisionEvent>()
-            .add_system(collision_detection.system());
+            .add_system(
+                movement
+                    .system()
+                    .label("movement")
+                    .before("world_transforms")
+                    .before("game_logic_sync"),
+            )
+            .add_system(
+                world_transforms
+                    .system()
+                    .label("world_transforms")
+                    .before("collision_detection")
+                    .before("game_logic_sync"),
+            )
+            .add_system(
+                collision_detection
+                    .system()
+                    .label("collision_detection")
+                    .before("game_logic_sync"),
+            );
     }
 }
 
@@ -20,30 +48,65 @@ impl Plugin for PhysicsPlugin {
 /// [Sprite]s which:
 /// - have colliders (you can use the `collider_creator` example to create your own colliders)
 /// - have their `collision` flags set to `true`.
+///
+/// Collisions also occur between a sprite like that and the solid tiles of a
+/// [`Tilemap`](crate::tilemap::Tilemap). The tilemap's label is then the second label in the
+/// `pair`.
 #[derive(Debug, Clone)]
 pub struct CollisionEvent {
     pub state: CollisionState,
     pub pair: CollisionPair,
+    /// Details about how the two colliders overlap. Present on `Begin` and `Ongoing` events,
+    /// `None` on `End` events (since the colliders are no longer overlapping).
+    pub contact: Option<Contact>,
+}
+
+/// Details about how two overlapping colliders overlap. Everything is in world space and
+/// relative to the order of the sprites in the [`CollisionPair`]: the normal points from the
+/// first sprite (`pair.0`) toward the second sprite (`pair.1`).
+///
+/// For example, to push the first sprite out of the second sprite, move it by
+/// `-contact.mtv()`. To push the second sprite out of the first, move it by `contact.mtv()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Unit vector pointing from the first sprite toward the second sprite along the axis of
+    /// least overlap
+    pub normal: Vec2,
+    /// How far the colliders overlap along `normal`
+    pub depth: f32,
+    /// An approximation of the point where the colliders touch: the center of the region where
+    /// they overlap
+    pub point: Vec2,
+}
+
+impl Contact {
+    /// The minimum translation vector: the shortest movement that would separate the two
+    /// colliders, if it were applied to the second sprite (or negated and applied to the first).
+    pub fn mtv(&self) -> Vec2 {
+        self.normal * self.depth
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum CollisionState {
+    /// The colliders started overlapping this frame
     Begin,
+    /// The colliders were already overlapping, and still are. These events are only generated if
+    /// [`EngineState::ongoing_collision_events`] is set to `true`.
+    Ongoing,
+    /// The colliders stopped overlapping this frame
     End,
 }
 
 impl CollisionState {
     pub fn is_begin(&self) -> bool {
-        match self {
-            CollisionState::Begin => true,
-            CollisionState::End => false,
-        }
+        matches!(self, CollisionState::Begin)
+    }
+    pub fn is_ongoing(&self) -> bool {
+        matches!(self, CollisionState::Ongoing)
     }
     pub fn is_end(&self) -> bool {
-        match self {
-            CollisionState::Begin => false,
-            CollisionState::End => true,
-        }
+        matches!(self, CollisionState::End)
     }
 }
 
@@ -87,26 +150,254 @@ impl Hash for CollisionPair {
     }
 }
 
-// system - detect collisions and generate the collision events
+/// How a sprite reacts physically when its collider overlaps another sprite's collider. Sprites
+/// need their `collision` fields set to `true` for this to have any effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyType {
+    /// Collisions are only reported, the engine doesn't move anything. This is the default.
+    NoBody,
+    /// Never moved by collisions. Dynamic sprites are pushed out of static sprites. Use this for
+    /// walls, barriers, and the like.
+    Static,
+    /// Pushed out of any static sprite it overlaps, and its velocity is reflected off of the static
+    /// sprite according to the [`restitution`](Sprite::restitution) of the two sprites.
+    Dynamic,
+}
+
+impl Default for BodyType {
+    fn default() -> Self {
+        BodyType::NoBody
+    }
+}
+
+// system - move and rotate sprites according to their velocities and accelerations
+fn movement(time: Res<Time>, delta_override: Res<DeltaOverride>, mut query: Query<&mut Sprite>) {
+    let delta = delta_override.delta(&time).as_secs_f32();
+    for mut sprite in query.iter_mut() {
+        // Don't touch sprites that aren't moving, so they don't show up as changed
+        if !sprite.is_moving() {
+            continue;
+        }
+        move_sprite(&mut sprite, delta);
+    }
+}
+
+// Move a sprite forward in time by `delta` seconds
+fn move_sprite(sprite: &mut Sprite, delta: f32) {
+    sprite.velocity += sprite.acceleration * delta;
+    if sprite.drag > 0.0 {
+        sprite.velocity *= (-sprite.drag * delta).exp();
+    }
+    if let Some(max_speed) = sprite.max_speed {
+        sprite.velocity = sprite.velocity.clamp_length_max(max_speed);
+    }
+    sprite.translation += sprite.velocity * delta;
+    sprite.rotation += sprite.angular_velocity * delta;
+}
+
+// system - work out where each sprite is in world space, now that sprites (and their parents) have
+// moved
+fn world_transforms(
+    mut query_set: QuerySet<(Query<&Sprite>, Query<(&mut Sprite, &mut Transform)>)>,
+) {
+    let world_transforms = sprite::world_transforms(query_set.q0().iter());
+    for (mut sprite, mut transform) in query_set.q1_mut().iter_mut() {
+        let world_transform = match world_transforms.get(&sprite.label) {
+            Some(world_transform) => *world_transform,
+            None => continue,
+        };
+        let current = WorldTransform {
+            translation: sprite.world_translation,
+            rotation: sprite.world_rotation,
+            scale: sprite.world_scale,
+        };
+        // Don't touch sprites that didn't move, so they don't show up as changed
+        if current != world_transform {
+            sprite.set_world_transform(world_transform);
+            *transform = sprite.bevy_transform();
+        }
+    }
+}
+
+// A sprite's collider transformed into world space, along with its axis-aligned bounding box. The
+// collider is made up of one or more convex parts.
+struct WorldCollider<'a> {
+    entity: Entity,
+    sprite: &'a Sprite,
+    parts: Vec<Shape>,
+    min: Vec2,
+    max: Vec2,
+}
+
+impl<'a> WorldCollider<'a> {
+    fn new(entity: Entity, sprite: &'a Sprite) -> Option<Self> {
+        let parts = world_parts(sprite);
+        if parts.is_empty() {
+            return None;
+        }
+        let (min, max) = parts.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), shape| {
+                let radius = Vec2::splat(shape.radius);
+                shape.points.iter().fold((min, max), |(min, max), &p| {
+                    (min.min(p - radius), max.max(p + radius))
+                })
+            },
+        );
+        Some(Self {
+            entity,
+            sprite,
+            parts,
+            min,
+            max,
+        })
+    }
+}
+
+// system - detect collisions, generate the collision events, and push dynamic sprites out of static
+// sprites
 fn collision_detection(
     mut existing_collisions: Local<HashSet<CollisionPair>>,
     mut collision_events: EventWriter<CollisionEvent>,
-    query: Query<&Sprite>,
+    mut engine_state: ResMut<EngineState>,
+    mut query: Query<(Entity, &mut Sprite, &mut Transform)>,
 ) {
+    // If a collider was replaced since it was split into convex parts, split it again
+    for (_, mut sprite, _) in query.iter_mut() {
+        if sprite.collision && !sprite.convex_decomposition.is_for(&sprite.collider) {
+            sprite.convex_decomposition = ConvexDecomposition::new(&sprite.collider);
+        }
+    }
+
+    // Transform each collider into world space only once per frame
+    let mut world_colliders: Vec<WorldCollider> = query
+        .iter()
+        .filter(|(_, sprite, _)| sprite.collision)
+        .filter_map(|(entity, sprite, _)| WorldCollider::new(entity, sprite))
+        .collect();
+
+    // Broad phase: sweep and prune. Sort the colliders by the left edge of their bounding boxes,
+    // then sweep from left to right. Only colliders whose bounding boxes overlap move on to the
+    // (much more expensive) polygon intersection test.
+    world_colliders.sort_by(|a, b| a.min.x.partial_cmp(&b.min.x).unwrap_or(Ordering::Equal));
     let mut current_collisions = HashSet::<CollisionPair>::new();
-    'outer: for sprite1 in query.iter().filter(|a| a.collision) {
-        for sprite2 in query.iter().filter(|a| a.collision) {
-            if sprite1.label == sprite2.label {
-                // We only need to compare one half of the matrix triangle
-                continue 'outer;
+    let mut contacts = HashMap::<CollisionPair, Contact>::new();
+    // Dynamic sprites to push out of static sprites: the entity, the minimum translation vector to
+    // push it by, and the restitution to bounce with
+    let mut pushes = Vec::<(Entity, Vec2, f32)>::new();
+    for (idx, collider1) in world_colliders.iter().enumerate() {
+        for collider2 in world_colliders[idx + 1..].iter() {
+            if collider2.min.x > collider1.max.x {
+                // Everything else in the list starts even further to the right
+                break;
+            }
+            if collider2.min.y > collider1.max.y || collider1.min.y > collider2.max.y {
+                continue;
             }
-            if Collider::colliding(sprite1, sprite2) {
-                current_collisions
-                    .insert(CollisionPair(sprite1.label.clone(), sprite2.label.clone()));
+            if !collider1.sprite.can_collide_with(collider2.sprite) {
+                continue;
+            }
+            if let Some((normal, depth, shape1, shape2)) =
+                deepest_overlap(&collider1.parts, &collider2.parts)
+            {
+                let pair = CollisionPair(
+                    collider1.sprite.label.clone(),
+                    collider2.sprite.label.clone(),
+                );
+                // Unless ongoing collisions are being reported, only beginning collisions report
+                // their contact, so don't bother with the contact point for the others
+                if engine_state.ongoing_collision_events || !existing_collisions.contains(&pair) {
+                    let point = contact_point(shape1, shape2, normal, depth);
+                    contacts.insert(
+                        pair.clone(),
+                        Contact {
+                            normal,
+                            depth,
+                            point,
+                        },
+                    );
+                }
+                current_collisions.insert(pair);
+                if collider1.sprite.sensor || collider2.sprite.sensor {
+                    // Sensors only report collisions
+                    continue;
+                }
+                let restitution = collider1
+                    .sprite
+                    .restitution
+                    .max(collider2.sprite.restitution);
+                match (collider1.sprite.body_type, collider2.sprite.body_type) {
+                    (BodyType::Dynamic, BodyType::Static) => {
+                        pushes.push((collider1.entity, -normal * depth, restitution))
+                    }
+                    (BodyType::Static, BodyType::Dynamic) => {
+                        pushes.push((collider2.entity, normal * depth, restitution))
+                    }
+                    _ => {}
+                }
             }
         }
     }
 
+    // Sprites against the solid tiles of the tilemaps. Tiles are static, so dynamic sprites are
+    // pushed out of them.
+    for tilemap in engine_state.tilemaps.values() {
+        if !tilemap.collision {
+            continue;
+        }
+        for collider in world_colliders.iter() {
+            let sprite = collider.sprite;
+            if !sprite.collision_mask.is_empty()
+                && !sprite.collision_mask.contains(&tilemap.collision_group)
+            {
+                continue;
+            }
+            let tiles: Vec<Shape> = tilemap
+                .solid_cells_in(collider.min, collider.max)
+                .into_iter()
+                .map(|corners| Shape {
+                    points: corners.to_vec(),
+                    radius: 0.0,
+                })
+                .collect();
+            let (normal, depth, part, tile) = match deepest_overlap(&collider.parts, &tiles) {
+                Some(overlap) => overlap,
+                None => continue,
+            };
+            let pair = CollisionPair(sprite.label.clone(), tilemap.label.clone());
+            if engine_state.ongoing_collision_events || !existing_collisions.contains(&pair) {
+                let point = contact_point(part, tile, normal, depth);
+                contacts.insert(
+                    pair.clone(),
+                    Contact {
+                        normal,
+                        depth,
+                        point,
+                    },
+                );
+            }
+            current_collisions.insert(pair);
+            if sprite.sensor || sprite.body_type != BodyType::Dynamic {
+                continue;
+            }
+            // Being pushed out of one tile can leave the sprite in another one (like in a corner),
+            // so keep pushing out of the deepest tile until the sprite is clear of them all
+            let mut mtv = -normal * depth;
+            for _ in 0..MAX_TILE_PUSHES {
+                let moved: Vec<Shape> = collider
+                    .parts
+                    .iter()
+                    .map(|part| part.translated(mtv))
+                    .collect();
+                match deepest_overlap(&moved, &tiles) {
+                    Some((normal, depth, _, _)) => mtv -= normal * depth,
+                    None => break,
+                }
+            }
+            pushes.push((collider.entity, mtv, sprite.restitution));
+        }
+    }
+
     let beginning_collisions: Vec<_> = current_collisions
         .difference(&existing_collisions)
         .cloned()
@@ -115,8 +406,19 @@ fn collision_detection(
     collision_events.send_batch(beginning_collisions.iter().map(|p| CollisionEvent {
         state: CollisionState::Begin,
         pair: p.clone(),
+        contact: contacts.get(p).copied(),
     }));
 
+    if engine_state.ongoing_collision_events {
+        collision_events.send_batch(current_collisions.intersection(&existing_collisions).map(
+            |p| CollisionEvent {
+                state: CollisionState::Ongoing,
+                pair: p.clone(),
+                contact: contacts.get(p).copied(),
+            },
+        ));
+    }
+
     for beginning_collision in beginning_collisions {
         existing_collisions.insert(beginning_collision);
     }
@@ -129,11 +431,68 @@ fn collision_detection(
     collision_events.send_batch(ending_collisions.iter().map(|p| CollisionEvent {
         state: CollisionState::End,
         pair: p.clone(),
+        contact: None,
     }));
 
     for ending_collision in ending_collisions {
         let _ = existing_collisions.remove(&ending_collision);
     }
+
+    engine_state.current_collisions = current_collisions;
+
+    // Collision response. Push each dynamic sprite out of the static sprites it overlaps, and
+    // bounce it off of them.
+    if pushes.is_empty() {
+        return;
+    }
+    // A sprite touching several static sprites at once (like two walls in a corner) is pushed by
+    // all of them, so gather up each sprite's pushes
+    let mut pushes_by_entity = HashMap::<Entity, Vec<(Vec2, f32)>>::new();
+    for (entity, mtv, restitution) in pushes {
+        pushes_by_entity
+            .entry(entity)
+            .or_default()
+            .push((mtv, restitution));
+    }
+    // The world rotation and scale of every sprite, by label, to find the parents of pushed sprites
+    let parents: HashMap<String, (f32, f32)> = query
+        .iter()
+        .map(|(_, sprite, _)| {
+            (
+                sprite.label.clone(),
+                (sprite.world_rotation, sprite.world_scale),
+            )
+        })
+        .collect();
+    for (entity, pushes) in pushes_by_entity {
+        if let Ok((_, mut sprite, mut transform)) = query.get_mut(entity) {
+            // The push is in world space, but an attached sprite's translation and velocity are
+            // relative to its parent. A sprite without a (living) parent is relative to the world.
+            let (parent_rotation, parent_scale) = sprite
+                .parent
+                .as_ref()
+                .and_then(|label| parents.get(label))
+                .copied()
+                .unwrap_or((0.0, 1.0));
+            if parent_scale == 0.0 {
+                // The parent has shrunk to nothing, so moving the sprite relative to it does nothing
+                continue;
+            }
+            let mtv = combine_pushes(pushes.iter().map(|&(mtv, _)| mtv));
+            sprite.world_translation += mtv;
+            sprite.translation += rotate(mtv, -parent_rotation) / parent_scale;
+            for (mtv, restitution) in pushes {
+                // Only bounce if the sprite is moving into the static sprite, not already away
+                // from it (say, because it already bounced off of another one just like it)
+                let normal = rotate(mtv.normalize_or_zero(), -parent_rotation);
+                let speed_into = sprite.velocity.dot(normal);
+                if speed_into < 0.0 {
+                    sprite.velocity -= normal * speed_into * (1.0 + restitution);
+                }
+            }
+            *transform = sprite.bevy_transform();
+        }
+    }
 }
 
 /// Represents the collider (or lack thereof) of a sprite. Two sprites need to have colliders AND
@@ -142,6 +501,18 @@ fn collision_detection(
 pub enum Collider {
     NoCollider,
     Poly(Vec<Vec2>),
+    /// A perfect circle
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// A line segment from `start` to `end`, expanded outward by `radius` in every direction (so it
+    /// has rounded ends). Handy for long, rounded things like pills, worms, and characters.
+    Capsule {
+        start: Vec2,
+        end: Vec2,
+        radius: f32,
+    },
 }
 
 impl Default for Collider {
@@ -186,20 +557,68 @@ impl Collider {
         Self::Poly(points)
     }
     /// Generate a 16-vertex polygon circle approximation. 16 was chosen as the default as it works
-    /// quite well with the circular sprites in the asset pack.
+    /// quite well with the circular sprites in the asset pack. For a perfect circle, see
+    /// [`circle_exact`](Collider::circle_exact).
     pub fn circle(radius: f32) -> Self {
         Self::circle_custom(radius, 16)
     }
+    /// Generate a perfect circle collider centered on the sprite. To approximate a circle with a
+    /// polygon instead, see [`circle`](Collider::circle) or
+    /// [`circle_custom`](Collider::circle_custom).
+    pub fn circle_exact(radius: f32) -> Self {
+        Self::Circle {
+            center: Vec2::ZERO,
+            radius,
+        }
+    }
+    /// Generate a capsule collider: a line segment from `start` to `end`, expanded outward by
+    /// `radius` in every direction.
+    pub fn capsule<T: Into<Vec2>>(start: T, end: T, radius: f32) -> Self {
+        Self::Capsule {
+            start: start.into(),
+            end: end.into(),
+            radius,
+        }
+    }
     /// Whether or not the collider is a `Collider::Poly`.
     pub fn is_poly(&self) -> bool {
         matches!(self, Self::Poly(_))
     }
-    /// Whether the points in the collider represent a convex polygon (not concave or complex).
-    /// This is important, because Rusty Engine's collision detection doesn't work correctly unless
-    /// colliders are convex polygons.
+    /// Whether or not the collider is a `Collider::Circle`.
+    pub fn is_circle(&self) -> bool {
+        matches!(self, Self::Circle { .. })
+    }
+    /// Whether or not the collider is a `Collider::Capsule`.
+    pub fn is_capsule(&self) -> bool {
+        matches!(self, Self::Capsule { .. })
+    }
+    /// Split the collider into convex parts, since Rusty Engine's collision detection only works
+    /// on convex shapes. A convex collider (including every circle and capsule) is returned as-is,
+    /// as a single part. A concave polygon is cut up into as few convex polygons as we can
+    /// reasonably manage. If a polygon's edges cross each other, it can't be split up, so it is
+    /// returned as-is (and collisions with it won't be accurate). `NoCollider` has no parts at all.
+    ///
+    /// You don't need to call this yourself. Sprites split their colliders up automatically when
+    /// they are loaded or created.
+    pub fn convex_parts(&self) -> Vec<Collider> {
+        match self {
+            Collider::NoCollider => Vec::new(),
+            Collider::Poly(points) if !self.is_convex() => convex_decomposition(points)
+                .map(|parts| parts.into_iter().map(Collider::Poly).collect())
+                .unwrap_or_else(|| vec![self.clone()]),
+            _ => vec![self.clone()],
+        }
+    }
+    /// Whether the collider is a convex shape: a circle, a capsule, or a polygon that is not
+    /// concave or complex. Collision detection only works directly on convex shapes, so other
+    /// polygons need to be split into convex parts first (see
+    /// [`convex_parts`](Collider::convex_parts)).
     ///
     /// Implementation based on Rory Daulton's answer on https://stackoverflow.com/questions/471962/how-do-i-efficiently-determine-if-a-polygon-is-convex-non-convex-or-complex?answertab=votes#tab-top
     pub fn is_convex(&self) -> bool {
+        if self.is_circle() || self.is_capsule() {
+            return true;
+        }
         if let Collider::Poly(points) = self {
             let length = points.len();
             if length < 3 {
@@ -260,77 +679,992 @@ impl Collider {
         }
         false
     }
-    fn rotated(&self, rotation: f32) -> Vec<Vec2> {
-        let mut rotated_points = Vec::new();
-        if let Self::Poly(points) = self {
-            let sin = rotation.sin();
-            let cos = rotation.cos();
-            for point in points.iter() {
-                rotated_points.push(Vec2::new(
-                    point.x * cos - point.y * sin,
-                    point.x * sin + point.y * cos,
-                ));
+    // The outline of the collider, relative to the sprite. Circles and the rounded ends of
+    // capsules are approximated with line segments.
+    fn outline(&self) -> Vec<Vec2> {
+        match self {
+            Collider::NoCollider => Vec::new(),
+            Collider::Poly(points) => points.clone(),
+            Collider::Circle { center, radius } => arc(*center, *radius, 0.0, TAU, 32),
+            Collider::Capsule { start, end, radius } => {
+                let direction = *end - *start;
+                let angle = direction.y.atan2(direction.x);
+                let mut points = arc(*end, *radius, angle - PI / 2.0, PI, 16);
+                points.extend(arc(*start, *radius, angle + PI / 2.0, PI, 16));
+                points
             }
         }
-        rotated_points
     }
     #[doc(hidden)]
     // Used internally to scale colliders to match a sprite's current transform
     pub fn relative_to(&self, sprite: &Sprite) -> Vec<Vec2> {
-        self.rotated(sprite.rotation)
-            .iter()
-            .map(|&v| v * sprite.scale + sprite.translation) // scale & translation
-            .collect()
+        to_world(&self.outline(), &Placement::of(sprite))
     }
     pub fn colliding(sprite1: &Sprite, sprite2: &Sprite) -> bool {
-        use Collider::*;
-        if let NoCollider = sprite1.collider {
-            return false;
-        }
-        if let NoCollider = sprite2.collider {
-            return false;
-        }
-        if sprite1.collider.is_poly() && sprite2.collider.is_poly() {
-            let poly1 = sprite1.collider.relative_to(sprite1);
-            let poly2 = sprite2.collider.relative_to(sprite2);
-            // Polygon intersection algorithm adapted from
-            // https://stackoverflow.com/questions/10962379/how-to-check-intersection-between-2-rotated-rectangles
-            for poly in vec![poly1.clone(), poly2.clone()] {
-                for (idx, &p1) in poly.iter().enumerate() {
-                    let p2 = poly[(idx + 1) % poly.len()];
-                    let normal = Vec2::new(p2.y - p1.y, p1.x - p2.x);
-
-                    let mut min_a = None;
-                    let mut max_a = None;
-                    for &p in poly1.iter() {
-                        let projected = normal.x * p.x + normal.y * p.y;
-                        if min_a.is_none() || projected < min_a.unwrap() {
-                            min_a = Some(projected);
-                        }
-                        if max_a.is_none() || projected > max_a.unwrap() {
-                            max_a = Some(projected);
-                        }
-                    }
+        deepest_overlap(&world_parts(sprite1), &world_parts(sprite2)).is_some()
+    }
+    /// If the two sprites' colliders are overlapping, returns the [`Contact`] describing how they
+    /// overlap, with the normal pointing from `sprite1` toward `sprite2`. Otherwise returns `None`.
+    pub fn contact(sprite1: &Sprite, sprite2: &Sprite) -> Option<Contact> {
+        let parts1 = world_parts(sprite1);
+        let parts2 = world_parts(sprite2);
+        let (normal, depth, shape1, shape2) = deepest_overlap(&parts1, &parts2)?;
+        Some(Contact {
+            normal,
+            depth,
+            point: contact_point(shape1, shape2, normal, depth),
+        })
+    }
+}
 
-                    let mut min_b = None;
-                    let mut max_b = None;
-                    for &p in poly2.iter() {
-                        let projected = normal.x * p.x + normal.y * p.y;
-                        if min_b.is_none() || projected < min_b.unwrap() {
-                            min_b = Some(projected);
-                        }
-                        if max_b.is_none() || projected > max_b.unwrap() {
-                            max_b = Some(projected);
-                        }
-                    }
+// Points along an arc of a circle, starting at angle `start` and sweeping counterclockwise by
+// `sweep` radians. Full circles don't repeat the first point at the end.
+fn arc(center: Vec2, radius: f32, start: f32, sweep: f32, segments: usize) -> Vec<Vec2> {
+    let count = if sweep >= TAU { segments } else { segments + 1 };
+    (0..count)
+        .map(|idx| {
+            let angle = start + sweep * idx as f32 / segments as f32;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
 
-                    if max_a < min_b || max_b < min_a {
-                        return false;
-                    }
+// One convex part of a collider, in world space: a convex polygon (or a line segment, or a single
+// point) expanded outward in every direction by `radius`. A circle is a single point with a radius,
+// and a capsule is a line segment with a radius.
+struct Shape {
+    points: Vec<Vec2>,
+    radius: f32,
+}
+
+impl Shape {
+    fn new(part: &Collider, placement: &Placement) -> Self {
+        let (points, radius) = match part {
+            Collider::NoCollider => (Vec::new(), 0.0),
+            Collider::Poly(points) => (points.clone(), 0.0),
+            // A stretched circle is an ellipse, which a radius can't describe, so fall back to
+            // the polygon outline
+            Collider::Circle { .. } | Collider::Capsule { .. } if !placement.is_uniform() => {
+                (part.outline(), 0.0)
+            }
+            Collider::Circle { center, radius } => (vec![*center], *radius),
+            Collider::Capsule { start, end, radius } => (vec![*start, *end], *radius),
+        };
+        Self {
+            points: to_world(&points, placement),
+            radius: radius * placement.scale.x.abs(),
+        }
+    }
+    fn is_rounded(&self) -> bool {
+        self.radius > 0.0
+    }
+    // The same shape, moved by `offset`
+    fn translated(&self, offset: Vec2) -> Self {
+        Self {
+            points: self.points.iter().map(|&point| point + offset).collect(),
+            radius: self.radius,
+        }
+    }
+    // Whether a point is inside (or on the edge of) the shape
+    fn contains(&self, point: Vec2) -> bool {
+        let inside_points = self.points.len() >= 3 && point_in_polygon(point, &self.points);
+        inside_points || self.closest_point(point).distance(point) <= self.radius
+    }
+    // Where a ray starting outside of the shape first hits it, if it does before `max_distance`.
+    // `direction` must be unit length. Returns the distance along the ray and the surface normal.
+    fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<(f32, Vec2)> {
+        let mut closest: Option<(f32, Vec2)> = None;
+        let mut consider = |distance: f32, normal: Vec2| {
+            if distance <= max_distance && closest.map_or(true, |(closest, _)| distance < closest) {
+                closest = Some((distance, normal));
+            }
+        };
+        // The flat sides: each edge, pushed outward by the radius. We don't know which way is
+        // outward, so try both sides of each edge. Only sides facing the ray can be hit first.
+        for (idx, &p1) in self.points.iter().enumerate() {
+            let p2 = self.points[(idx + 1) % self.points.len()];
+            let normal = Vec2::new(p2.y - p1.y, p1.x - p2.x).normalize_or_zero();
+            for normal in [normal, -normal] {
+                if normal.dot(direction) >= 0.0 {
+                    continue;
+                }
+                let offset = normal * self.radius;
+                if let Some(distance) = ray_segment(origin, direction, p1 + offset, p2 + offset) {
+                    consider(distance, normal);
                 }
             }
-            return true;
         }
-        false
+        // The rounded corners
+        if self.is_rounded() {
+            for &center in self.points.iter() {
+                if let Some(distance) = ray_circle(origin, direction, center, self.radius) {
+                    let hit = origin + direction * distance;
+                    consider(distance, (hit - center).normalize_or_zero());
+                }
+            }
+        }
+        closest
+    }
+    // The smallest and largest projections of the shape onto a unit-length axis
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        let (min, max) = project(&self.points, axis);
+        (min - self.radius, max + self.radius)
+    }
+    // The unit normals of the edges between the points
+    fn edge_normals(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.points
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, &p1)| {
+                let p2 = self.points[(idx + 1) % self.points.len()];
+                let normal = Vec2::new(p2.y - p1.y, p1.x - p2.x).normalize_or_zero();
+                // Repeated points don't make an edge
+                if normal == Vec2::ZERO {
+                    None
+                } else {
+                    Some(normal)
+                }
+            })
+    }
+    // The point on the edges between the points (ignoring the radius) closest to `point`
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(idx, &p1)| {
+                let p2 = self.points[(idx + 1) % self.points.len()];
+                closest_point_on_segment(point, p1, p2)
+            })
+            .fold(self.points[0], |closest, p| {
+                if p.distance_squared(point) < closest.distance_squared(point) {
+                    p
+                } else {
+                    closest
+                }
+            })
+    }
+    // The furthest point of the shape in a direction. If several points are equally far, they are
+    // averaged, so a flat side facing `direction` gives the middle of the side.
+    fn support(&self, direction: Vec2) -> Vec2 {
+        let (_, max) = project(&self.points, direction);
+        let furthest: Vec<Vec2> = self
+            .points
+            .iter()
+            .filter(|p| p.dot(direction) >= max - 1e-3)
+            .copied()
+            .collect();
+        centroid(&furthest) + direction * self.radius
+    }
+}
+
+// How far along a ray (with a unit-length `direction`) it crosses the line segment from `a` to
+// `b`, if it does
+fn ray_segment(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2) -> Option<f32> {
+    let ab = b - a;
+    let denominator = direction.perp_dot(ab);
+    if denominator == 0.0 {
+        // Parallel
+        return None;
+    }
+    let distance = (a - origin).perp_dot(ab) / denominator;
+    let along_segment = (a - origin).perp_dot(direction) / denominator;
+    if distance >= 0.0 && (0.0..=1.0).contains(&along_segment) {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+// How far along a ray (with a unit-length `direction`) it first hits a circle, if it does
+fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = origin - center;
+    let b = offset.dot(direction);
+    let c = offset.length_squared() - radius * radius;
+    if c > 0.0 && b > 0.0 {
+        // Outside of the circle and pointing away from it
+        return None;
+    }
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    Some((-b - discriminant.sqrt()).max(0.0))
+}
+
+// The point on the line segment from `a` to `b` closest to `point`
+pub(crate) fn closest_point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+// A collider split up into convex parts, along with the collider it was split up from, so we can
+// tell when a sprite's collider has been replaced and needs to be split up again. Sprites are
+// cloned back and forth every frame, so the parts are shared rather than copied.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConvexDecomposition(Arc<DecomposedCollider>);
+
+#[derive(Debug, Default)]
+struct DecomposedCollider {
+    source: Collider,
+    parts: Vec<Collider>,
+}
+
+impl ConvexDecomposition {
+    pub(crate) fn new(collider: &Collider) -> Self {
+        Self(Arc::new(DecomposedCollider {
+            source: collider.clone(),
+            parts: collider.convex_parts(),
+        }))
+    }
+    pub(crate) fn is_for(&self, collider: &Collider) -> bool {
+        &self.0.source == collider
+    }
+    fn parts(&self) -> &[Collider] {
+        &self.0.parts
+    }
+}
+
+// Where a collider is in world space
+struct Placement {
+    translation: Vec2,
+    rotation: f32,
+    // The scale along each axis (before rotation). Negative on an axis the collider is mirrored
+    // along.
+    scale: Vec2,
+}
+
+impl Placement {
+    fn of(sprite: &Sprite) -> Self {
+        let world = sprite.world_transform();
+        Self {
+            translation: world.translation,
+            rotation: world.rotation,
+            scale: sprite.mirror() * sprite.stretch * world.scale,
+        }
+    }
+    // Whether the collider is scaled the same amount along both axes
+    fn is_uniform(&self) -> bool {
+        self.scale.x.abs() == self.scale.y.abs()
+    }
+}
+
+// Scale (and mirror), rotate, and translate points from a collider into world space
+fn to_world(points: &[Vec2], placement: &Placement) -> Vec<Vec2> {
+    points
+        .iter()
+        .map(|point| rotate(*point * placement.scale, placement.rotation) + placement.translation)
+        .collect()
+}
+
+// Rotate a point counterclockwise around the origin by `angle` radians
+pub(crate) fn rotate(point: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    Vec2::new(point.x * cos - point.y * sin, point.x * sin + point.y * cos)
+}
+
+// The convex parts of a sprite's collider, in world space
+fn world_parts(sprite: &Sprite) -> Vec<Shape> {
+    let decomposition = &sprite.convex_decomposition;
+    if decomposition.is_for(&sprite.collider) {
+        place_parts(decomposition.parts(), &Placement::of(sprite))
+    } else {
+        // The collider was replaced since it was last split up
+        place_parts(&sprite.collider.convex_parts(), &Placement::of(sprite))
+    }
+}
+
+// Convex parts of a collider, placed in world space
+fn place_parts(parts: &[Collider], placement: &Placement) -> Vec<Shape> {
+    parts
+        .iter()
+        .map(|part| Shape::new(part, placement))
+        // A polygon with no points yet (like while it's being created) can't collide
+        .filter(|shape| !shape.points.is_empty())
+        .collect()
+}
+
+/// Where a ray hit a sprite's collider. See
+/// [`EngineState::raycast`](crate::prelude::EngineState::raycast).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaycastHit {
+    /// The label of the sprite that was hit
+    pub label: String,
+    /// How far the ray traveled before it hit the sprite
+    pub distance: f32,
+    /// Where the ray hit the sprite, in world space
+    pub point: Vec2,
+    /// Unit vector pointing straight out of the sprite's collider where the ray hit it
+    pub normal: Vec2,
+}
+
+// The closest place a ray hits any of the sprites' colliders before `max_distance`. Sprites whose
+// colliders contain `origin` are skipped, so rays can be cast from inside of a sprite.
+pub(crate) fn raycast<'a>(
+    sprites: impl Iterator<Item = &'a Sprite>,
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+) -> Option<RaycastHit> {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return None;
+    }
+    let mut closest: Option<RaycastHit> = None;
+    for sprite in sprites {
+        let shapes = world_parts(sprite);
+        if shapes.iter().any(|shape| shape.contains(origin)) {
+            continue;
+        }
+        for shape in shapes.iter() {
+            let max_distance = closest.as_ref().map_or(max_distance, |hit| hit.distance);
+            if let Some((distance, normal)) = shape.raycast(origin, direction, max_distance) {
+                closest = Some(RaycastHit {
+                    label: sprite.label.clone(),
+                    distance,
+                    point: origin + direction * distance,
+                    normal,
+                });
+            }
+        }
+    }
+    closest
+}
+
+// The labels of the sprites whose colliders contain a point
+pub(crate) fn point_query<'a>(
+    sprites: impl Iterator<Item = &'a Sprite>,
+    point: Vec2,
+) -> Vec<String> {
+    sprites
+        .filter(|sprite| {
+            world_parts(sprite)
+                .iter()
+                .any(|shape| shape.contains(point))
+        })
+        .map(|sprite| sprite.label.clone())
+        .collect()
+}
+
+// The labels of the sprites whose colliders overlap a collider placed in world space
+pub(crate) fn overlap_query<'a>(
+    sprites: impl Iterator<Item = &'a Sprite>,
+    collider: &Collider,
+    transform: WorldTransform,
+    stretch: Vec2,
+) -> Vec<String> {
+    let placement = Placement {
+        translation: transform.translation,
+        rotation: transform.rotation,
+        scale: stretch * transform.scale,
+    };
+    let shapes = place_parts(&collider.convex_parts(), &placement);
+    sprites
+        .filter(|sprite| deepest_overlap(&world_parts(sprite), &shapes).is_some())
+        .map(|sprite| sprite.label.clone())
+        .collect()
+}
+
+// Of all the pairs of convex parts of two colliders that overlap, the pair that overlaps the most.
+// Returns the normal and depth of the overlap (see `shapes_overlap`), and the two parts.
+fn deepest_overlap<'a>(
+    parts1: &'a [Shape],
+    parts2: &'a [Shape],
+) -> Option<(Vec2, f32, &'a Shape, &'a Shape)> {
+    let mut deepest: Option<(Vec2, f32, &Shape, &Shape)> = None;
+    for part1 in parts1 {
+        for part2 in parts2 {
+            if let Some((normal, depth)) = shapes_overlap(part1, part2) {
+                if deepest.map_or(true, |(_, deepest_depth, _, _)| depth > deepest_depth) {
+                    deepest = Some((normal, depth, part1, part2));
+                }
+            }
+        }
+    }
+    deepest
+}
+
+// Whether the turn from `a` through `b` to `c` is counterclockwise (positive), clockwise
+// (negative), or straight (zero), ignoring tiny rounding errors
+fn turn(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    let cross = (b - a)
+        .normalize_or_zero()
+        .perp_dot((c - b).normalize_or_zero());
+    if cross.abs() < 1e-5 {
+        0.0
+    } else {
+        cross
+    }
+}
+
+// Split a simple polygon (one whose edges don't cross) of either winding into convex polygons.
+// First the polygon is cut into triangles by ear clipping, then neighboring triangles are merged
+// back together wherever the result is still convex (the Hertel-Mehlhorn algorithm). Returns `None`
+// if the polygon can't be split up, which happens when its edges cross.
+fn convex_decomposition(points: &[Vec2]) -> Option<Vec<Vec<Vec2>>> {
+    // Work with counterclockwise points, without any repeats
+    let mut points: Vec<Vec2> = points.to_vec();
+    points.dedup();
+    while points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 || edges_cross(&points) {
+        return None;
+    }
+    let twice_area: f32 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    if twice_area < 0.0 {
+        points.reverse();
+    }
+
+    // Ear clipping. An "ear" is a convex corner whose triangle doesn't contain any other corner
+    // of the polygon, so it can be clipped off without changing the rest of the polygon.
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut polygons: Vec<Vec<usize>> = Vec::new();
+    while remaining.len() > 3 {
+        let length = remaining.len();
+        let mut clipped = false;
+        for idx in 0..length {
+            let prev = remaining[(idx + length - 1) % length];
+            let curr = remaining[idx];
+            let next = remaining[(idx + 1) % length];
+            let (a, b, c) = (points[prev], points[curr], points[next]);
+            let turn = turn(a, b, c);
+            if turn == 0.0 {
+                // A corner that isn't really a corner doesn't enclose anything
+                remaining.remove(idx);
+                clipped = true;
+                break;
+            }
+            if turn < 0.0 {
+                continue;
+            }
+            let triangle = [a, b, c];
+            let is_ear = !remaining.iter().any(|&other| {
+                let p = points[other];
+                !triangle.contains(&p) && point_in_polygon(p, &triangle)
+            });
+            if is_ear {
+                polygons.push(vec![prev, curr, next]);
+                remaining.remove(idx);
+                clipped = true;
+                break;
+            }
+        }
+        if !clipped {
+            return None;
+        }
+    }
+    if turn(
+        points[remaining[0]],
+        points[remaining[1]],
+        points[remaining[2]],
+    ) > 0.0
+    {
+        polygons.push(remaining);
+    }
+    if polygons.is_empty() {
+        // All the points were in a line
+        return None;
+    }
+
+    // Merge neighboring polygons whenever the result is convex
+    'merging: loop {
+        for idx1 in 0..polygons.len() {
+            for idx2 in (idx1 + 1)..polygons.len() {
+                if let Some(merged) = merge_convex(&polygons[idx1], &polygons[idx2], &points) {
+                    polygons[idx1] = merged;
+                    polygons.swap_remove(idx2);
+                    continue 'merging;
+                }
+            }
+        }
+        break;
+    }
+
+    Some(
+        polygons
+            .iter()
+            .map(|polygon| polygon.iter().map(|&idx| points[idx]).collect())
+            .collect(),
+    )
+}
+
+// Whether any two edges of a polygon that aren't next to each other cross
+fn edges_cross(points: &[Vec2]) -> bool {
+    let length = points.len();
+    for idx1 in 0..length {
+        // Edges next to each other share a point, so skip them (the first and last edges are
+        // next to each other, too)
+        for idx2 in (idx1 + 2)..length {
+            if idx1 == 0 && idx2 == length - 1 {
+                continue;
+            }
+            let a1 = points[idx1];
+            let a2 = points[(idx1 + 1) % length];
+            let b1 = points[idx2];
+            let b2 = points[(idx2 + 1) % length];
+            if segment_intersection(a1, a2, b1, b2).is_some() {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// If two counterclockwise polygons (made of indexes into `points`) share an edge, and joining them
+// along that edge makes a convex polygon, returns the joined polygon
+fn merge_convex(poly1: &[usize], poly2: &[usize], points: &[Vec2]) -> Option<Vec<usize>> {
+    for (idx1, &a) in poly1.iter().enumerate() {
+        let b = poly1[(idx1 + 1) % poly1.len()];
+        // The shared edge runs in the opposite direction in the other polygon
+        let idx2 = match poly2.iter().position(|&p| p == b) {
+            Some(idx2) if poly2[(idx2 + 1) % poly2.len()] == a => idx2,
+            _ => continue,
+        };
+        // Go all the way around poly1 from b to a, then the rest of the way around poly2
+        let mut merged: Vec<usize> = (1..=poly1.len())
+            .map(|offset| poly1[(idx1 + offset) % poly1.len()])
+            .collect();
+        merged.extend((2..poly2.len()).map(|offset| poly2[(idx2 + offset) % poly2.len()]));
+        let length = merged.len();
+        let convex = (0..length).all(|idx| {
+            turn(
+                points[merged[idx]],
+                points[merged[(idx + 1) % length]],
+                points[merged[(idx + 2) % length]],
+            ) >= 0.0
+        });
+        return if convex { Some(merged) } else { None };
+    }
+    None
+}
+
+// Combine the pushes out of several static sprites into a single push. Adding them all up would
+// push too far when they point the same way (like two floor tiles side by side both pushing up), so
+// each push only adds however much of it the pushes before it didn't already cover. Deeper pushes
+// go first.
+fn combine_pushes<I: Iterator<Item = Vec2>>(pushes: I) -> Vec2 {
+    let mut pushes: Vec<Vec2> = pushes.collect();
+    pushes.sort_by(|a, b| {
+        b.length_squared()
+            .partial_cmp(&a.length_squared())
+            .unwrap_or(Ordering::Equal)
+    });
+    let mut combined = Vec2::ZERO;
+    for push in pushes {
+        let direction = push.normalize_or_zero();
+        let covered = combined.dot(direction).max(0.0);
+        let depth = push.length();
+        if covered < depth {
+            combined += direction * (depth - covered);
+        }
+    }
+    combined
+}
+
+// The smallest and largest projections of the points onto an axis
+fn project(poly: &[Vec2], axis: Vec2) -> (f32, f32) {
+    poly.iter()
+        .map(|p| p.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), projected| {
+            (min.min(projected), max.max(projected))
+        })
+}
+
+// The average of the points
+fn centroid(poly: &[Vec2]) -> Vec2 {
+    poly.iter().sum::<Vec2>() / poly.len() as f32
+}
+
+// If two convex shapes (in world space) overlap, returns the unit normal pointing from shape1
+// toward shape2 along the axis of least overlap, and the amount of overlap along that axis.
+//
+// This is the separating axis theorem (SAT). Polygon intersection algorithm adapted from
+// https://stackoverflow.com/questions/10962379/how-to-check-intersection-between-2-rotated-rectangles
+//
+// For polygons, the edge normals are the only axes that need to be checked. Rounded shapes can also
+// be separated along the direction from a corner of one shape to the closest point on the other.
+fn shapes_overlap(shape1: &Shape, shape2: &Shape) -> Option<(Vec2, f32)> {
+    let mut axes: Vec<Vec2> = shape1.edge_normals().chain(shape2.edge_normals()).collect();
+    if shape1.is_rounded() || shape2.is_rounded() {
+        for (from, to) in [(shape1, shape2), (shape2, shape1)] {
+            for &point in from.points.iter() {
+                let axis = (to.closest_point(point) - point).normalize_or_zero();
+                if axis != Vec2::ZERO {
+                    axes.push(axis);
+                }
+            }
+        }
+    }
+    if axes.is_empty() {
+        // Two circles at the exact same place. Any direction is as good as any other.
+        axes.push(Vec2::X);
+    }
+    let mut least_overlap: Option<(Vec2, f32)> = None;
+    for axis in axes {
+        let (min_a, max_a) = shape1.project(axis);
+        let (min_b, max_b) = shape2.project(axis);
+        if max_a < min_b || max_b < min_a {
+            // Found a gap, so the shapes aren't overlapping
+            return None;
+        }
+        let overlap = max_a.min(max_b) - min_a.max(min_b);
+        if least_overlap.map_or(true, |(_, least)| overlap < least) {
+            least_overlap = Some((axis, overlap));
+        }
+    }
+    let (mut normal, depth) = least_overlap?;
+    if (centroid(&shape2.points) - centroid(&shape1.points)).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+    Some((normal, depth))
+}
+
+// Whether a point is inside (or on the edge of) a convex polygon of either winding
+pub(crate) fn point_in_polygon(point: Vec2, poly: &[Vec2]) -> bool {
+    let mut sign = 0.0;
+    for (idx, &p1) in poly.iter().enumerate() {
+        let p2 = poly[(idx + 1) % poly.len()];
+        let cross = (p2 - p1).perp_dot(point - p1);
+        if cross != 0.0 {
+            if sign == 0.0 {
+                sign = cross.signum();
+            } else if cross.signum() != sign {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Where two line segments cross, if they do
+fn segment_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Vec2> {
+    let da = a2 - a1;
+    let db = b2 - b1;
+    let denominator = da.perp_dot(db);
+    if denominator == 0.0 {
+        // Parallel
+        return None;
+    }
+    let t = (b1 - a1).perp_dot(db) / denominator;
+    let u = (b1 - a1).perp_dot(da) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(a1 + da * t)
+    } else {
+        None
+    }
+}
+
+// Approximate where two overlapping convex shapes touch. A rounded shape touches at its furthest
+// point toward the other shape, pulled back to the middle of the overlap.
+fn contact_point(shape1: &Shape, shape2: &Shape, normal: Vec2, depth: f32) -> Vec2 {
+    match (shape1.is_rounded(), shape2.is_rounded()) {
+        (false, false) => polygon_contact_point(&shape1.points, &shape2.points),
+        (true, false) => shape1.support(normal) - normal * depth * 0.5,
+        (false, true) => shape2.support(-normal) + normal * depth * 0.5,
+        (true, true) => (shape1.support(normal) + shape2.support(-normal)) * 0.5,
+    }
+}
+
+// Approximate where two overlapping convex polygons touch, by averaging the corners of the region
+// where they overlap: the corners of each polygon that are inside the other, plus the points where
+// their edges cross.
+fn polygon_contact_point(poly1: &[Vec2], poly2: &[Vec2]) -> Vec2 {
+    let mut corners: Vec<Vec2> = poly1
+        .iter()
+        .filter(|&&p| point_in_polygon(p, poly2))
+        .chain(poly2.iter().filter(|&&p| point_in_polygon(p, poly1)))
+        .copied()
+        .collect();
+    for (idx1, &a1) in poly1.iter().enumerate() {
+        let a2 = poly1[(idx1 + 1) % poly1.len()];
+        for (idx2, &b1) in poly2.iter().enumerate() {
+            let b2 = poly2[(idx2 + 1) % poly2.len()];
+            if let Some(p) = segment_intersection(a1, a2, b1, b2) {
+                corners.push(p);
+            }
+        }
+    }
+    if corners.is_empty() {
+        // Shouldn't happen for overlapping polygons, but just in case
+        return (centroid(poly1) + centroid(poly2)) * 0.5;
+    }
+    centroid(&corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::SpriteShape;
+
+    fn poly(points: &[(f32, f32)]) -> Vec<Vec2> {
+        points.iter().map(|&(x, y)| Vec2::new(x, y)).collect()
+    }
+
+    fn area(points: &[Vec2]) -> f32 {
+        let twice_area: f32 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        twice_area.abs() / 2.0
+    }
+
+    // Each part starting from its leftmost point, and the parts in order, so that decompositions
+    // can be compared regardless of where the polygon started
+    fn normalized(mut parts: Vec<Vec<Vec2>>) -> Vec<Vec<Vec2>> {
+        let by_position = |p: &Vec2, q: &Vec2| {
+            p.x.partial_cmp(&q.x)
+                .unwrap()
+                .then(p.y.partial_cmp(&q.y).unwrap())
+        };
+        for part in parts.iter_mut() {
+            let start = (0..part.len())
+                .min_by(|&a, &b| by_position(&part[a], &part[b]))
+                .unwrap();
+            part.rotate_left(start);
+        }
+        parts.sort_by(|a, b| by_position(&a[0], &b[0]).then(a.len().cmp(&b.len())));
+        parts
+    }
+
+    fn l_shape() -> Vec<Vec2> {
+        poly(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn convex_polygon_stays_in_one_piece() {
+        let hexagon = poly(&[
+            (2.0, 0.0),
+            (1.0, 1.5),
+            (-1.0, 1.5),
+            (-2.0, 0.0),
+            (-1.0, -1.5),
+            (1.0, -1.5),
+        ]);
+        let parts = convex_decomposition(&hexagon).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].len(), hexagon.len());
+        assert_eq!(Collider::Poly(hexagon).convex_parts().len(), 1);
+    }
+
+    #[test]
+    fn l_shape_splits_into_two_convex_parts() {
+        let parts = convex_decomposition(&l_shape()).unwrap();
+        assert_eq!(parts.len(), 2);
+        for part in parts.iter() {
+            assert!(Collider::Poly(part.clone()).is_convex());
+        }
+        let total_area: f32 = parts.iter().map(|part| area(part)).sum();
+        assert!((total_area - area(&l_shape())).abs() < 1e-5);
+    }
+
+    #[test]
+    fn clockwise_matches_counterclockwise() {
+        let counterclockwise = l_shape();
+        let mut clockwise = counterclockwise.clone();
+        clockwise.reverse();
+        assert_eq!(
+            normalized(convex_decomposition(&clockwise).unwrap()),
+            normalized(convex_decomposition(&counterclockwise).unwrap())
+        );
+    }
+
+    #[test]
+    fn degenerate_polygons_do_not_panic() {
+        let degenerate = [
+            poly(&[]),
+            poly(&[(1.0, 1.0)]),
+            poly(&[(0.0, 0.0), (1.0, 0.0)]),
+            poly(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]),
+            poly(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (1.5, 0.0)]),
+            poly(&[(0.0, 0.0), (0.0, 0.0), (0.0, 0.0)]),
+        ];
+        for points in degenerate.iter() {
+            assert_eq!(convex_decomposition(points), None);
+            assert_eq!(Collider::Poly(points.clone()).convex_parts().len(), 1);
+        }
+        // A point in the middle of an edge isn't a real corner
+        let square = poly(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        let parts = convex_decomposition(&square).unwrap();
+        assert_eq!(parts.len(), 1);
+    }
+
+    fn square(center: (f32, f32), half_size: f32) -> Shape {
+        let center = Vec2::new(center.0, center.1);
+        Shape {
+            points: vec![
+                center + Vec2::new(-half_size, -half_size),
+                center + Vec2::new(half_size, -half_size),
+                center + Vec2::new(half_size, half_size),
+                center + Vec2::new(-half_size, half_size),
+            ],
+            radius: 0.0,
+        }
+    }
+
+    #[test]
+    fn sprite_in_a_corner_is_pushed_out_once_per_direction() {
+        // Two floor tiles side by side, and a wall standing on the second one. The sprite has sunk
+        // 1 pixel into both floor tiles and 1 pixel into the wall.
+        let statics = [
+            square((0.0, 0.0), 10.0),
+            square((20.0, 0.0), 10.0),
+            square((26.0, 20.0), 10.0),
+        ];
+        let sprite = square((12.0, 14.0), 5.0);
+        let pushes: Vec<Vec2> = statics
+            .iter()
+            .filter_map(|wall| shapes_overlap(&sprite, wall))
+            .map(|(normal, depth)| -normal * depth)
+            .collect();
+        assert_eq!(pushes.len(), 3);
+
+        let push = combine_pushes(pushes.into_iter());
+        assert!((push - Vec2::new(-1.0, 1.0)).length() < 1e-5);
+        let moved = sprite.translated(push);
+        for wall in statics.iter() {
+            assert!(shapes_overlap(&moved, wall).map_or(true, |(_, depth)| depth < 1e-4));
+        }
+    }
+
+    #[test]
+    fn opposite_pushes_cancel_out() {
+        let push = combine_pushes([Vec2::new(2.0, 0.0), Vec2::new(-2.0, 0.0)].into_iter());
+        assert_eq!(push, Vec2::ZERO);
+    }
+
+    fn circle(center: (f32, f32), radius: f32) -> Shape {
+        Shape {
+            points: vec![Vec2::new(center.0, center.1)],
+            radius,
+        }
+    }
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).length() < 1e-4,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn overlapping_polygons_report_normal_depth_and_point() {
+        let left = square((0.0, 0.0), 10.0);
+        let right = square((15.0, 2.0), 10.0);
+        let (normal, depth) = shapes_overlap(&left, &right).unwrap();
+        // The normal points from the first shape toward the second, along the shallowest overlap
+        assert_near(normal, Vec2::X);
+        assert!((depth - 5.0).abs() < 1e-4);
+        // The middle of the region where they overlap
+        let point = contact_point(&left, &right, normal, depth);
+        assert_near(point, Vec2::new(7.5, 1.0));
+
+        let (normal, depth) = shapes_overlap(&right, &left).unwrap();
+        assert_near(normal, -Vec2::X);
+        assert!((depth - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn separated_shapes_do_not_overlap() {
+        let left = square((0.0, 0.0), 10.0);
+        assert!(shapes_overlap(&left, &square((25.0, 0.0), 10.0)).is_none());
+        assert!(shapes_overlap(&left, &circle((0.0, 20.0), 5.0)).is_none());
+        // Diagonally past the corner, where only the axis toward the corner finds the gap
+        assert!(shapes_overlap(&left, &circle((14.0, 14.0), 5.0)).is_none());
+    }
+
+    #[test]
+    fn rounded_shapes_report_normal_depth_and_point() {
+        let block = square((0.0, 0.0), 10.0);
+        let ball = circle((12.0, 0.0), 5.0);
+        let (normal, depth) = shapes_overlap(&block, &ball).unwrap();
+        assert_near(normal, Vec2::X);
+        assert!((depth - 3.0).abs() < 1e-4);
+        // Halfway into the overlap from the ball's edge
+        assert_near(
+            contact_point(&block, &ball, normal, depth),
+            Vec2::new(8.5, 0.0),
+        );
+
+        let other_ball = circle((0.0, 16.0), 10.0);
+        let (normal, depth) = shapes_overlap(&circle((0.0, 0.0), 10.0), &other_ball).unwrap();
+        assert_near(normal, Vec2::Y);
+        assert!((depth - 4.0).abs() < 1e-4);
+    }
+
+    fn moving_sprite() -> Sprite {
+        Sprite::from_shape("mover", SpriteShape::circle(5.0, Color::WHITE))
+    }
+
+    #[test]
+    fn velocity_moves_and_turns_sprites() {
+        let mut sprite = moving_sprite();
+        sprite.velocity = Vec2::new(10.0, -4.0);
+        sprite.angular_velocity = 2.0;
+        move_sprite(&mut sprite, 0.5);
+        assert_near(sprite.translation, Vec2::new(5.0, -2.0));
+        assert!((sprite.rotation - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn acceleration_changes_velocity_before_moving() {
+        let mut sprite = moving_sprite();
+        sprite.acceleration = Vec2::new(0.0, 10.0);
+        move_sprite(&mut sprite, 1.0);
+        assert_near(sprite.velocity, Vec2::new(0.0, 10.0));
+        assert_near(sprite.translation, Vec2::new(0.0, 10.0));
+        move_sprite(&mut sprite, 1.0);
+        assert_near(sprite.velocity, Vec2::new(0.0, 20.0));
+        assert_near(sprite.translation, Vec2::new(0.0, 30.0));
+    }
+
+    #[test]
+    fn drag_slows_sprites_down_the_same_no_matter_the_frame_rate() {
+        let mut one_frame = moving_sprite();
+        one_frame.velocity = Vec2::new(100.0, 0.0);
+        one_frame.drag = 1.0;
+        move_sprite(&mut one_frame, 1.0);
+        assert_near(
+            one_frame.velocity,
+            Vec2::new(100.0 / std::f32::consts::E, 0.0),
+        );
+
+        let mut many_frames = moving_sprite();
+        many_frames.velocity = Vec2::new(100.0, 0.0);
+        many_frames.drag = 1.0;
+        for _ in 0..60 {
+            move_sprite(&mut many_frames, 1.0 / 60.0);
+        }
+        // Give or take a little rounding error from all the frames
+        assert!((many_frames.velocity - one_frame.velocity).length() < 1e-3);
+    }
+
+    #[test]
+    fn max_speed_caps_velocity_without_changing_direction() {
+        let mut sprite = moving_sprite();
+        sprite.velocity = Vec2::new(30.0, 40.0);
+        sprite.acceleration = Vec2::new(3.0, 4.0);
+        sprite.max_speed = Some(10.0);
+        move_sprite(&mut sprite, 1.0);
+        assert_near(sprite.velocity, Vec2::new(6.0, 8.0));
+        assert_near(sprite.translation, Vec2::new(6.0, 8.0));
     }
 }
@@ -0,0 +1,297 @@
+//! Facilities for recording all the keyboard and mouse input of a game, along with the timing of
+//! every frame, and replaying it later. This makes it possible to reproduce a bug exactly: record a
+//! run where the bug happens, attach the recording to your bug report, and anyone can replay it
+//! with the same input on the same frames.
+//!
+//! Use `Game::record_input` and `Game::replay_input` to record and replay. Each frame is written
+//! to the recording as soon as it happens, so the recording is still useful if the game crashes.
+//!
+//! While replaying, real keyboard and mouse input is ignored, so the game sees exactly the input
+//! that was recorded. Once the recording runs out, the game continues normally.
+use crate::{
+    game::DeltaOverride,
+    keyboard::{KeyCode, KeyboardInput},
+    mouse::{CursorMoved, ElementState, MouseButton, MouseButtonInput, MouseMotion, MouseWheel},
+};
+use bevy::{app::Events, input::mouse::MouseScrollUnit, prelude::*, window::WindowId};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    time::Duration,
+};
+
+#[doc(hidden)]
+pub struct InputRecordingPlugin;
+
+impl Plugin for InputRecordingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<InputRecorder>()
+            .init_resource::<InputReplay>()
+            // Replayed input needs to be sent before Bevy processes input in `PreUpdate`. Real
+            // input is thrown away while replaying, so scripted input has to be sent afterwards.
+            .add_system_to_stage(
+                CoreStage::First,
+                replay_input.system().before("play_input_script"),
+            )
+            // Time is updated during `First`, so we record after that
+            .add_system_to_stage(CoreStage::PreUpdate, record_input.system());
+    }
+}
+
+/// Everything that happened during one frame
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct RecordedFrame {
+    delta: Duration,
+    keyboard_events: Vec<RecordedKey>,
+    mouse_button_events: Vec<RecordedMouseButton>,
+    /// In Bevy's window space
+    mouse_location_events: Vec<Vec2>,
+    /// In Bevy's window space
+    mouse_motion_events: Vec<Vec2>,
+    mouse_wheel_events: Vec<RecordedMouseWheel>,
+}
+
+// Bevy's input events can't be serialized, so we have our own versions of them
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct RecordedKey {
+    scan_code: u32,
+    key_code: Option<KeyCode>,
+    pressed: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct RecordedMouseButton {
+    button: MouseButton,
+    pressed: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+struct RecordedMouseWheel {
+    lines: bool,
+    x: f32,
+    y: f32,
+}
+
+fn is_pressed(state: ElementState) -> bool {
+    matches!(state, ElementState::Pressed)
+}
+
+fn element_state(pressed: bool) -> ElementState {
+    if pressed {
+        ElementState::Pressed
+    } else {
+        ElementState::Released
+    }
+}
+
+/// Writes each frame to a recording file. Doesn't do anything unless it has a file to write to.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    file: Option<File>,
+}
+
+impl InputRecorder {
+    /// Create (or overwrite) a recording file. `filepath` is relative to the current directory
+    /// (usually the root of your project), not the `assets/` directory. If the file can't be
+    /// created, an error is printed and nothing will be recorded.
+    pub fn new<P: AsRef<Path>>(filepath: P) -> Self {
+        match File::create(filepath.as_ref()) {
+            Ok(file) => Self { file: Some(file) },
+            Err(e) => {
+                eprintln!("failed creating input recording file: {}", e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// The frames to replay. Doesn't do anything unless it has frames to replay.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct InputReplay {
+    frames: Vec<RecordedFrame>,
+    next_frame: usize,
+}
+
+impl InputReplay {
+    /// Load a recording file. `filepath` is relative to the current directory (usually the root of
+    /// your project), not the `assets/` directory. If the file can't be read, an error is printed
+    /// and nothing will be replayed. If part of the file can't be parsed (for example, if the game
+    /// crashed while writing the last frame) an error is printed, and the frames up to that point
+    /// will be replayed.
+    pub fn from_file<P: AsRef<Path>>(filepath: P) -> Self {
+        let fh = match File::open(filepath.as_ref()) {
+            Ok(fh) => fh,
+            Err(e) => {
+                eprintln!("failed to open input recording file: {}", e);
+                return Self::default();
+            }
+        };
+        let mut frames = Vec::new();
+        for line in BufReader::new(fh).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("failed reading input recording file: {}", e);
+                    break;
+                }
+            };
+            match ron::de::from_str::<RecordedFrame>(&line) {
+                Ok(frame) => frames.push(frame),
+                Err(e) => {
+                    eprintln!(
+                        "failed deserializing frame from input recording file: {}",
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+        Self {
+            frames,
+            next_frame: 0,
+        }
+    }
+}
+
+// system - write everything that happened this frame to the recording file, if we are recording
+#[allow(clippy::too_many_arguments)]
+fn record_input(
+    mut input_recorder: ResMut<InputRecorder>,
+    time: Res<Time>,
+    delta_override: Res<DeltaOverride>,
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+) {
+    let file = match input_recorder.file.as_mut() {
+        Some(file) => file,
+        None => return,
+    };
+    let frame = RecordedFrame {
+        delta: delta_override.delta(&time),
+        keyboard_events: keyboard_input_events
+            .iter()
+            .map(|ev| RecordedKey {
+                scan_code: ev.scan_code,
+                key_code: ev.key_code,
+                pressed: is_pressed(ev.state),
+            })
+            .collect(),
+        mouse_button_events: mouse_button_events
+            .iter()
+            .map(|ev| RecordedMouseButton {
+                button: ev.button,
+                pressed: is_pressed(ev.state),
+            })
+            .collect(),
+        mouse_location_events: cursor_moved_events.iter().map(|ev| ev.position).collect(),
+        mouse_motion_events: mouse_motion_events.iter().map(|ev| ev.delta).collect(),
+        mouse_wheel_events: mouse_wheel_events
+            .iter()
+            .map(|ev| RecordedMouseWheel {
+                lines: matches!(ev.unit, MouseScrollUnit::Line),
+                x: ev.x,
+                y: ev.y,
+            })
+            .collect(),
+    };
+    // One frame per line, written right away, so a crash doesn't lose the frames leading up to it
+    let result = ron::ser::to_string(&frame)
+        .map_err(|e| e.to_string())
+        .and_then(|line| {
+            file.write_all(format!("{}\n", line).as_bytes())
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        eprintln!(
+            "failed writing to input recording file, recording stopped: {}",
+            e
+        );
+        input_recorder.file = None;
+    }
+}
+
+// system - replace the real input for this frame with the recorded input, and use the recorded
+// frame timing
+#[allow(clippy::too_many_arguments)]
+fn replay_input(
+    mut resume_delta_override: Local<Option<DeltaOverride>>,
+    mut input_replay: ResMut<InputReplay>,
+    mut delta_override: ResMut<DeltaOverride>,
+    mut keyboard_input_events: ResMut<Events<KeyboardInput>>,
+    mut mouse_button_events: ResMut<Events<MouseButtonInput>>,
+    mut cursor_moved_events: ResMut<Events<CursorMoved>>,
+    mut mouse_motion_events: ResMut<Events<MouseMotion>>,
+    mut mouse_wheel_events: ResMut<Events<MouseWheel>>,
+) {
+    if input_replay.frames.is_empty() {
+        return;
+    }
+    // Remember how time was advancing before the replay, so we can go back to it afterwards
+    let resume = *resume_delta_override.get_or_insert(*delta_override);
+    let frame = match input_replay.frames.get(input_replay.next_frame) {
+        Some(frame) => frame.clone(),
+        None => {
+            *delta_override = resume;
+            return;
+        }
+    };
+    input_replay.next_frame += 1;
+
+    delta_override.0 = Some(frame.delta);
+    // Any real input would make the game go differently than it did when it was recorded
+    discard_events(&mut keyboard_input_events);
+    discard_events(&mut mouse_button_events);
+    discard_events(&mut cursor_moved_events);
+    discard_events(&mut mouse_motion_events);
+    discard_events(&mut mouse_wheel_events);
+    keyboard_input_events.extend(frame.keyboard_events.iter().map(|ev| KeyboardInput {
+        scan_code: ev.scan_code,
+        key_code: ev.key_code,
+        state: element_state(ev.pressed),
+    }));
+    mouse_button_events.extend(frame.mouse_button_events.iter().map(|ev| MouseButtonInput {
+        button: ev.button,
+        state: element_state(ev.pressed),
+    }));
+    cursor_moved_events.extend(
+        frame
+            .mouse_location_events
+            .iter()
+            .map(|&position| CursorMoved {
+                id: WindowId::primary(),
+                position,
+            }),
+    );
+    mouse_motion_events.extend(
+        frame
+            .mouse_motion_events
+            .iter()
+            .map(|&delta| MouseMotion { delta }),
+    );
+    mouse_wheel_events.extend(frame.mouse_wheel_events.iter().map(|ev| MouseWheel {
+        unit: if ev.lines {
+            MouseScrollUnit::Line
+        } else {
+            MouseScrollUnit::Pixel
+        },
+        x: ev.x,
+        y: ev.y,
+    }));
+}
+
+// Throw away all the events that have been sent, read or not. Each update throws away the older of
+// the two buffers of events, so two updates throw away both. (`Events::clear` would throw them away
+// too, but event readers keep track of where they are by counting, and would skip over the events
+// sent afterwards.)
+fn discard_events<T: Send + Sync + 'static>(events: &mut Events<T>) {
+    events.update();
+    events.update();
+}
@@ -0,0 +1,379 @@
+//! Sprites drawn from simple shapes (rectangles, circles, regular polygons, and lines) instead of
+//! image files. Handy for prototyping a game before there is any art for it.
+//!
+//! A shape sprite is created with
+//! [`EngineState::add_shape`](crate::prelude::EngineState::add_shape), and gets a collider that
+//! matches its shape automatically.
+//!
+//! ```rust
+//! # use rusty_engine::prelude::*;
+//! # rusty_engine::init!();
+//! # fn main() {
+//! # let mut game = Game::new();
+//! let ship = game.add_shape(
+//!     "ship",
+//!     SpriteShape::regular_polygon(3, 30.0, Color::GREEN).with_outline(Color::WHITE, 3.0),
+//! );
+//! ship.rotation = UP;
+//! # }
+//! ```
+use crate::physics::{closest_point_on_segment, point_in_polygon, Collider};
+use bevy::{
+    prelude::*,
+    render::texture::{Extent3d, TextureDimension, TextureFormat},
+    utils::{HashMap, HashSet},
+};
+use std::f32::consts::TAU;
+
+// Empty pixels around the edge of a shape's texture, so antialiased edges don't get cut off
+const TEXTURE_MARGIN: f32 = 2.0;
+
+/// The geometry of a [`SpriteShape`]. Everything is in pixels, relative to the center of the
+/// sprite, before the sprite is scaled or rotated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShapeGeometry {
+    /// A rectangle `size.x` wide and `size.y` tall
+    Rectangle { size: Vec2 },
+    /// A circle
+    Circle { radius: f32 },
+    /// A regular polygon with `sides` sides (at least 3), with its corners `radius` away from the
+    /// center. The first corner points right, the direction a sprite with a rotation of `0.0`
+    /// faces.
+    RegularPolygon { sides: u32, radius: f32 },
+    /// A line from `start` to `end`, `thickness` wide, with rounded ends
+    Line {
+        start: Vec2,
+        end: Vec2,
+        thickness: f32,
+    },
+}
+
+/// A shape that a sprite is drawn as, instead of an image file. See the
+/// [module-level docs](crate::shape).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteShape {
+    /// What shape it is, and how big
+    pub geometry: ShapeGeometry,
+    /// The color to fill the shape with, if any
+    pub fill: Option<Color>,
+    /// The color and width (in pixels) of the outline, if any. The outline is drawn just inside
+    /// the edge of the shape.
+    pub outline: Option<(Color, f32)>,
+}
+
+impl SpriteShape {
+    /// A rectangle `size.x` wide and `size.y` tall, filled with `fill`
+    pub fn rectangle<T: Into<Vec2>>(size: T, fill: Color) -> Self {
+        Self::filled(ShapeGeometry::Rectangle { size: size.into() }, fill)
+    }
+
+    /// A circle filled with `fill`
+    pub fn circle(radius: f32, fill: Color) -> Self {
+        Self::filled(ShapeGeometry::Circle { radius }, fill)
+    }
+
+    /// A regular polygon with `sides` sides (at least 3), with its corners `radius` away from the
+    /// center, filled with `fill`. For example, `3` sides makes a triangle pointing right.
+    pub fn regular_polygon(sides: u32, radius: f32, fill: Color) -> Self {
+        Self::filled(
+            ShapeGeometry::RegularPolygon {
+                sides: sides.max(3),
+                radius,
+            },
+            fill,
+        )
+    }
+
+    /// A line from `start` to `end` (relative to the center of the sprite), `thickness` wide, with
+    /// rounded ends
+    pub fn line<T: Into<Vec2>>(start: T, end: T, thickness: f32, color: Color) -> Self {
+        Self::filled(
+            ShapeGeometry::Line {
+                start: start.into(),
+                end: end.into(),
+                thickness,
+            },
+            color,
+        )
+    }
+
+    fn filled(geometry: ShapeGeometry, fill: Color) -> Self {
+        Self {
+            geometry,
+            fill: Some(fill),
+            outline: None,
+        }
+    }
+
+    /// Add an outline `width` pixels wide. Returns the shape, so you can chain calls.
+    pub fn with_outline(mut self, color: Color, width: f32) -> Self {
+        self.outline = Some((color, width));
+        self
+    }
+
+    /// Remove the fill, so only the outline is drawn. Returns the shape, so you can chain calls.
+    pub fn without_fill(mut self) -> Self {
+        self.fill = None;
+        self
+    }
+
+    /// A collider that matches the shape
+    pub fn collider(&self) -> Collider {
+        match self.geometry {
+            ShapeGeometry::Rectangle { size } => Collider::rect(-size / 2.0, size / 2.0),
+            ShapeGeometry::Circle { radius } => Collider::circle_exact(radius),
+            ShapeGeometry::RegularPolygon { .. } => Collider::poly(&self.corners()),
+            ShapeGeometry::Line {
+                start,
+                end,
+                thickness,
+            } => Collider::capsule(start, end, thickness / 2.0),
+        }
+    }
+
+    // The corners of a rectangle or regular polygon
+    fn corners(&self) -> Vec<Vec2> {
+        match self.geometry {
+            ShapeGeometry::Rectangle { size } => {
+                let half = size / 2.0;
+                vec![
+                    Vec2::new(-half.x, -half.y),
+                    Vec2::new(half.x, -half.y),
+                    Vec2::new(half.x, half.y),
+                    Vec2::new(-half.x, half.y),
+                ]
+            }
+            ShapeGeometry::RegularPolygon { sides, radius } => {
+                let sides = sides.max(3);
+                (0..sides)
+                    .map(|i| {
+                        let angle = TAU * i as f32 / sides as f32;
+                        Vec2::new(angle.cos(), angle.sin()) * radius
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // How far a point is from the edge of the shape. Negative inside the shape.
+    fn signed_distance(&self, point: Vec2) -> f32 {
+        match self.geometry {
+            ShapeGeometry::Circle { radius } => point.length() - radius,
+            ShapeGeometry::Line {
+                start,
+                end,
+                thickness,
+            } => closest_point_on_segment(point, start, end).distance(point) - thickness / 2.0,
+            _ => {
+                let corners = self.corners();
+                let distance = corners
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &p1)| {
+                        let p2 = corners[(idx + 1) % corners.len()];
+                        closest_point_on_segment(point, p1, p2).distance(point)
+                    })
+                    .fold(f32::MAX, f32::min);
+                if point_in_polygon(point, &corners) {
+                    -distance
+                } else {
+                    distance
+                }
+            }
+        }
+    }
+
+    // How far the shape reaches from the center of the sprite along each axis
+    fn half_extents(&self) -> Vec2 {
+        match self.geometry {
+            ShapeGeometry::Circle { radius } => Vec2::splat(radius),
+            ShapeGeometry::Line {
+                start,
+                end,
+                thickness,
+            } => start.abs().max(end.abs()) + Vec2::splat(thickness / 2.0),
+            _ => self
+                .corners()
+                .iter()
+                .fold(Vec2::ZERO, |extents, corner| extents.max(corner.abs())),
+        }
+    }
+
+    /// Draw the shape into a texture, centered
+    #[doc(hidden)]
+    pub fn texture(&self) -> Texture {
+        let half_extents = self.half_extents() + Vec2::splat(TEXTURE_MARGIN);
+        let width = (half_extents.x * 2.0).ceil().max(1.0) as u32;
+        let height = (half_extents.y * 2.0).ceil().max(1.0) as u32;
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for row in 0..height {
+            for column in 0..width {
+                // The center of the pixel. The first row of the texture is the top.
+                let point = Vec2::new(
+                    column as f32 + 0.5 - width as f32 / 2.0,
+                    height as f32 / 2.0 - (row as f32 + 0.5),
+                );
+                data.extend_from_slice(&self.pixel(self.signed_distance(point)));
+            }
+        }
+        Texture::new(
+            Extent3d::new(width, height, 1),
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    // The color of a pixel whose center is `distance` from the edge of the shape
+    fn pixel(&self, distance: f32) -> [u8; 4] {
+        // How much of a pixel is inside an edge, for smooth (antialiased) edges
+        let coverage = |distance: f32| (0.5 - distance).clamp(0.0, 1.0);
+        let outline_width = self.outline.map_or(0.0, |(_, width)| width);
+        let layers = [
+            (self.fill, coverage(distance + outline_width)),
+            (
+                self.outline.map(|(color, _)| color),
+                coverage(distance) - coverage(distance + outline_width),
+            ),
+        ];
+        // Add up the layers with premultiplied alpha
+        let mut rgba = [0.0; 4];
+        for (color, coverage) in layers {
+            if let Some(color) = color {
+                let alpha = color.a() * coverage;
+                rgba[0] += color.r() * alpha;
+                rgba[1] += color.g() * alpha;
+                rgba[2] += color.b() * alpha;
+                rgba[3] += alpha;
+            }
+        }
+        let alpha = rgba[3];
+        if alpha > 0.0 {
+            for channel in rgba.iter_mut().take(3) {
+                *channel /= alpha;
+            }
+        }
+        rgba.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+/// The textures that shapes have been drawn into, so that sprites with the same shape share one
+/// texture instead of each drawing their own
+#[doc(hidden)]
+#[derive(Default)]
+pub struct ShapeTextures {
+    textures: HashMap<ShapeKey, Handle<Texture>>,
+    // The shapes whose textures were handed out since the last time unused textures were
+    // forgotten. Sprites spawned this frame don't show up in queries until the next frame, so
+    // these are kept for one more round.
+    handed_out: HashSet<ShapeKey>,
+}
+
+impl ShapeTextures {
+    /// The texture of a shape, drawing it if no sprite has had this shape yet
+    pub fn get_or_add(
+        &mut self,
+        shape: &SpriteShape,
+        textures: &mut Assets<Texture>,
+    ) -> Handle<Texture> {
+        self.get_or_insert_with(shape, || textures.add(shape.texture()))
+    }
+
+    fn get_or_insert_with<F>(&mut self, shape: &SpriteShape, add_texture: F) -> Handle<Texture>
+    where
+        F: FnOnce() -> Handle<Texture>,
+    {
+        let key = ShapeKey::new(shape);
+        self.handed_out.insert(key.clone());
+        self.textures.entry(key).or_insert_with(add_texture).clone()
+    }
+
+    /// Forget the textures of shapes that none of `shown_shapes` have anymore, so they can be
+    /// freed. Textures handed out since the last call are kept, since the sprites they were
+    /// handed out to may not be shown yet. Cheap when there is nothing to forget.
+    pub fn forget_unused<'a, I>(&mut self, shown_shapes: I)
+    where
+        I: Iterator<Item = &'a SpriteShape> + Clone,
+    {
+        let handed_out = std::mem::take(&mut self.handed_out);
+        // There can't be any unused textures unless there are more textures than shapes shown
+        if self.textures.len() <= shown_shapes.clone().count() {
+            return;
+        }
+        let in_use: HashSet<ShapeKey> = shown_shapes.map(ShapeKey::new).collect();
+        self.textures
+            .retain(|key, _| in_use.contains(key) || handed_out.contains(key));
+    }
+}
+
+// The exact bits of a shape, to look it up by (floats can't be hashed)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ShapeKey(Vec<u32>);
+
+impl ShapeKey {
+    fn new(shape: &SpriteShape) -> Self {
+        let mut bits = Vec::new();
+        let mut push = |values: &[f32]| bits.extend(values.iter().map(|value| value.to_bits()));
+        match shape.geometry {
+            ShapeGeometry::Rectangle { size } => push(&[0.0, size.x, size.y]),
+            ShapeGeometry::Circle { radius } => push(&[1.0, radius]),
+            ShapeGeometry::RegularPolygon { sides, radius } => push(&[2.0, sides as f32, radius]),
+            ShapeGeometry::Line {
+                start,
+                end,
+                thickness,
+            } => push(&[3.0, start.x, start.y, end.x, end.y, thickness]),
+        }
+        match shape.fill {
+            Some(color) => push(&color.as_rgba_f32()),
+            None => push(&[-1.0]),
+        }
+        match shape.outline {
+            Some((color, width)) => {
+                push(&color.as_rgba_f32());
+                push(&[width]);
+            }
+            None => push(&[-1.0]),
+        }
+        Self(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprites_added_on_consecutive_frames_share_a_texture() {
+        let shape = SpriteShape::rectangle((20.0, 10.0), Color::RED);
+        let mut shape_textures = ShapeTextures::default();
+        let mut drawn = 0;
+        let mut add_texture = || {
+            drawn += 1;
+            Handle::default()
+        };
+
+        // Frame 1: the first sprite is spawned, but isn't shown by the time textures are forgotten
+        let first = shape_textures.get_or_insert_with(&shape, &mut add_texture);
+        shape_textures.forget_unused(std::iter::empty());
+
+        // Frame 2: the first sprite is shown now, and a second sprite with the same shape spawns
+        let second = shape_textures.get_or_insert_with(&shape, &mut add_texture);
+        shape_textures.forget_unused(std::iter::once(&shape));
+
+        assert_eq!(first, second);
+        assert_eq!(drawn, 1);
+    }
+
+    #[test]
+    fn unused_textures_are_forgotten() {
+        let shape = SpriteShape::circle(5.0, Color::BLUE);
+        let mut shape_textures = ShapeTextures::default();
+        shape_textures.get_or_insert_with(&shape, Handle::default);
+        shape_textures.forget_unused(std::iter::empty());
+        assert_eq!(shape_textures.textures.len(), 1);
+        shape_textures.forget_unused(std::iter::empty());
+        assert!(shape_textures.textures.is_empty());
+    }
+}
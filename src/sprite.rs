@@ -1,5 +1,10 @@
-use crate::physics::Collider;
-use bevy::prelude::*;
+use crate::{
+    animation::SpriteAnimation,
+    camera::ScreenAnchor,
+    physics::{rotate, BodyType, Collider, ConvexDecomposition},
+    shape::{ShapeTextures, SpriteShape},
+};
+use bevy::{prelude::*, utils::HashMap};
 
 /// An [`Sprite`] is the basic abstraction for something that can be seen and interacted with.
 /// Players, obstacles, etc. are all sprites.
@@ -7,30 +12,138 @@ use bevy::prelude::*;
 pub struct Sprite {
     /// READONLY: A way to identify a sprite.
     pub label: String,
-    /// READONLY: File used for this sprite's image
+    /// SYNCED: File used for this sprite's image, relative to `assets/sprite`. Set it to a new
+    /// filepath (or a [`SpritePreset`], with `.into()`) to change the image. The sprite's collider
+    /// is then reloaded from the collider file that matches the new image.
     pub filepath: PathBuf,
+    /// SYNCED: A shape to draw the sprite as, instead of the image at `filepath`. Changing it
+    /// redraws the sprite and replaces its collider with one that matches the new shape. See
+    /// [`SpriteShape`].
+    pub shape: Option<SpriteShape>,
     /// READONLY: File used for this sprite's collider. Note that this file will not exist if the
     /// sprite does not have a collider, but if you set the `collider` field to a collider and then
     /// call the `write_collider` method, the file will be written for you!
     pub collider_filepath: PathBuf,
     /// SYNCED: Where you are in 2D game space. Positive x is right. Positive y is up. (0.0, 0.0) is the
-    /// center of the screen.
+    /// center of the screen. If the sprite has a `parent`, this is relative to the parent instead.
     pub translation: Vec2,
-    /// SYNCED: Depth of the sprite. 0.0 (back) to 999.0 (front)
+    /// SYNCED: Depth of the sprite. 0.0 (back) to 999.0 (front). This is never relative to a
+    /// `parent`.
     pub layer: f32,
-    /// SYNCED: Direction you face in radians. See constants UP, DOWN, LEFT, RIGHT
+    /// SYNCED: Direction you face in radians. See constants UP, DOWN, LEFT, RIGHT. If the sprite has
+    /// a `parent`, this is relative to the parent's rotation.
     pub rotation: f32,
-    /// SYNCED: 1.0 is the normal 100%
+    /// SYNCED: 1.0 is the normal 100%. If the sprite has a `parent`, this is multiplied by the
+    /// parent's scale.
     pub scale: f32,
+    /// SYNCED: Extra scaling along the sprite's own x and y axes (before rotation), on top of
+    /// `scale`. The default, `(1.0, 1.0)`, doesn't stretch the sprite at all. For example,
+    /// `Vec2::new(4.0, 1.0)` makes a sprite four times as long without making it any thicker. The
+    /// collider is stretched to match.
+    pub stretch: Vec2,
+    /// SYNCED: The label of another sprite to attach this sprite to, if any. An attached sprite
+    /// follows its parent around: its `translation`, `rotation`, and `scale` are relative to the
+    /// parent's (but not its `stretch` or flipping). When the parent is removed, so is this sprite.
+    /// If there is no sprite with the parent's label (say, it is misspelled or hasn't been added
+    /// yet), this sprite is placed as if it had no parent. Handy for things like a turret on a car, or wheels on a vehicle.
+    pub parent: Option<String>,
+    /// SYNCED: Set this to put the sprite on the UI layer, where it stays put on the screen even as
+    /// the camera moves, zooms, or rotates. `translation` is then an offset in pixels from this
+    /// point on the screen, which keeps up with the window being resized. Sprites attached to a
+    /// sprite on the UI layer are on the UI layer too (their own `screen_anchor` is ignored).
+    /// Colliders are still in world space, so UI sprites should usually leave `collision` off.
+    pub screen_anchor: Option<ScreenAnchor>,
+    /// READONLY: Where the sprite is in world space, taking its `parent` into account. Without a
+    /// parent, this is the same as `translation`. Updated by the engine every frame.
+    pub world_translation: Vec2,
+    /// READONLY: The sprite's rotation in world space, taking its `parent` into account. Updated by
+    /// the engine every frame.
+    pub world_rotation: f32,
+    /// READONLY: The sprite's scale in world space, taking its `parent` into account. Updated by the
+    /// engine every frame.
+    pub world_scale: f32,
+    /// SYNCED: How fast the sprite is moving, in pixels per second. The engine moves the sprite by
+    /// its velocity every frame, before your game logic runs.
+    pub velocity: Vec2,
+    /// SYNCED: How fast the sprite is rotating, in radians per second. Positive is
+    /// counterclockwise.
+    pub angular_velocity: f32,
+    /// SYNCED: How fast the velocity changes, in pixels per second per second
+    pub acceleration: Vec2,
+    /// SYNCED: How quickly the sprite slows down on its own. `0.0` (the default) means no drag. At
+    /// `1.0`, the sprite loses about 63% of its speed every second.
+    pub drag: f32,
+    /// SYNCED: If set, the sprite's speed (the length of its velocity) is never allowed to go over
+    /// this many pixels per second
+    pub max_speed: Option<f32>,
     /// Whether or not to calculate collisions
     pub collision: bool,
-    /// Relative to translation
+    /// SYNCED: The collision group this sprite belongs to, like `"player"`, `"bullet"`, or
+    /// `"wall"`. Other sprites can use their `collision_mask` to decide whether to collide with this
+    /// group. Defaults to an empty string (no particular group).
+    pub collision_group: String,
+    /// SYNCED: The collision groups this sprite collides with. Two sprites only collide if each
+    /// one's mask includes the other's group. An empty mask (the default) collides with every group.
+    /// For example, a bullet with a mask of `vec!["player".into(), "wall".into()]` never collides
+    /// with other bullets, and the pair is never even tested.
+    pub collision_mask: Vec<String>,
+    /// SYNCED: How the sprite reacts physically to collisions. By default, collisions are only
+    /// reported. See [`BodyType`] for the other options.
+    pub body_type: BodyType,
+    /// SYNCED: How bouncy the sprite is when a dynamic sprite hits a static sprite. `0.0` (the
+    /// default) means the dynamic sprite stops moving into the static sprite (it may still slide
+    /// along it). `1.0` means a perfect bounce, with no speed lost. The bouncier of the two sprites
+    /// is used.
+    pub restitution: f32,
+    /// SYNCED: If `true`, the sprite's collider is a sensor (also known as a trigger). Sensors
+    /// still generate collision events, but never push or get pushed by anything, no matter what
+    /// their `body_type` is. Handy for finish lines, pickup zones, and the like.
+    pub sensor: bool,
+    /// SYNCED: A color to multiply the image's colors by. The default, `Color::WHITE`, leaves the
+    /// image alone. `Color::RED` makes a great damage flash!
+    pub tint: Color,
+    /// SYNCED: How opaque the sprite is. `1.0` (the default) is fully opaque, and `0.0` is
+    /// invisible. Handy for fade-outs and ghosts.
+    pub alpha: f32,
+    /// SYNCED: If `true`, the image is mirrored horizontally (along with the collider)
+    pub flip_x: bool,
+    /// SYNCED: If `true`, the image is mirrored vertically (along with the collider)
+    pub flip_y: bool,
+    /// SYNCED: The animation the sprite is playing, if any. While an animation is playing, its
+    /// current frame is shown instead of the sprite's own image (or shape). See
+    /// [`play_animation`](Sprite::play_animation).
+    pub animation: Option<SpriteAnimation>,
+    /// Relative to translation. Concave polygons are automatically split into convex parts for
+    /// collision detection.
     pub collider: Collider,
+    // The collider, split into convex parts
+    pub(crate) convex_decomposition: ConvexDecomposition,
+    // The shape the collider was generated from, if it was
+    pub(crate) collider_shape: Option<SpriteShape>,
+    // The image currently shown, which is a frame of the animation while one is playing
+    pub(crate) shown_image: SpriteImage,
     #[doc(hidden)]
     // force people to use new()
     phantom: PhantomData<()>,
 }
 
+// The collider file that goes with an image file, and the collider loaded from it
+fn collider_for(filepath: &Path) -> (PathBuf, Collider) {
+    let mut collider_filepath = filepath.to_path_buf();
+    collider_filepath.set_extension("collider");
+    let actual_collider_filepath = PathBuf::from("assets/sprite").join(&collider_filepath);
+    let collider = if actual_collider_filepath.exists() {
+        read_collider_from_file(actual_collider_filepath.as_path())
+    } else {
+        eprintln!(
+            "warning: could not find collider file {} -- consider creating one with the `collider_creator` example.",
+            actual_collider_filepath.to_string_lossy()
+        );
+        Collider::NoCollider
+    };
+    (collider_filepath, collider)
+}
+
 fn read_collider_from_file(filepath: &Path) -> Collider {
     match File::open(filepath) {
         Ok(fh) => match ron::de::from_reader::<_, Collider>(fh) {
@@ -57,40 +170,219 @@ impl Sprite {
     /// programmatically create a [`Collider`], set the sprite's `.collider` field to it, and call
     /// the sprite's `.write_collider()` method.  All presets have collider files already.
     pub fn new<S: Into<String>, P: Into<PathBuf>>(label: S, file_or_preset: P) -> Self {
-        let label = label.into();
         let filepath = file_or_preset.into();
-        let mut collider_filepath = filepath.clone();
-        collider_filepath.set_extension("collider");
-        let actual_collider_filepath = PathBuf::from("assets/sprite").join(&collider_filepath);
-        let collider = if actual_collider_filepath.exists() {
-            read_collider_from_file(actual_collider_filepath.as_path())
-        } else {
-            eprintln!(
-                "warning: could not find collider file {} -- consider creating one with the `collider_creator` example.",
-                actual_collider_filepath.to_string_lossy()
-            );
-            Collider::NoCollider
+        let (collider_filepath, collider) = collider_for(&filepath);
+        Self::with_collider(label.into(), filepath, None, collider_filepath, collider)
+    }
+
+    /// Create a sprite drawn as a [`SpriteShape`] instead of an image file. It gets a collider that
+    /// matches the shape, so there's no need for a collider file. `label` should be a unique string,
+    /// just like for [`Sprite::new`].
+    pub fn from_shape<S: Into<String>>(label: S, shape: SpriteShape) -> Self {
+        let collider = shape.collider();
+        Self::with_collider(
+            label.into(),
+            PathBuf::new(),
+            Some(shape),
+            PathBuf::new(),
+            collider,
+        )
+    }
+
+    fn with_collider(
+        label: String,
+        filepath: PathBuf,
+        shape: Option<SpriteShape>,
+        collider_filepath: PathBuf,
+        collider: Collider,
+    ) -> Self {
+        let shown_image = match &shape {
+            Some(shape) => SpriteImage::Shape(shape.clone()),
+            None => SpriteImage::File(filepath.clone()),
         };
         Self {
             label,
             filepath,
+            collider_shape: shape.clone(),
+            shape,
             collider_filepath,
             translation: Vec2::default(),
             layer: f32::default(),
             rotation: f32::default(),
             scale: 1.0,
+            stretch: Vec2::ONE,
+            parent: None,
+            screen_anchor: None,
+            world_translation: Vec2::ZERO,
+            world_rotation: 0.0,
+            world_scale: 1.0,
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            acceleration: Vec2::ZERO,
+            drag: 0.0,
+            max_speed: None,
             collision: false,
+            collision_group: String::new(),
+            collision_mask: Vec::new(),
+            body_type: BodyType::NoBody,
+            restitution: 0.0,
+            sensor: false,
+            tint: Color::WHITE,
+            alpha: 1.0,
+            flip_x: false,
+            flip_y: false,
+            animation: None,
+            convex_decomposition: ConvexDecomposition::new(&collider),
             collider,
+            shown_image,
             phantom: PhantomData,
         }
     }
 
+    /// Whether the sprite is moving, rotating, or accelerating on its own
+    pub fn is_moving(&self) -> bool {
+        self.velocity != Vec2::ZERO
+            || self.angular_velocity != 0.0
+            || self.acceleration != Vec2::ZERO
+    }
+
+    /// Whether this sprite and `other` are allowed to collide, according to their collision groups
+    /// and masks. This doesn't check the `collision` fields, or whether the colliders overlap.
+    pub fn can_collide_with(&self, other: &Sprite) -> bool {
+        let accepts =
+            |mask: &[String], group: &str| mask.is_empty() || mask.iter().any(|g| g == group);
+        accepts(&self.collision_mask, &other.collision_group)
+            && accepts(&other.collision_mask, &self.collision_group)
+    }
+
+    /// Start playing the animation registered as `name` with
+    /// [`EngineState::add_animation`](crate::prelude::EngineState::add_animation). If the sprite is
+    /// already playing that animation, it keeps going (and is unpaused) instead of starting over,
+    /// so it is fine to call this every frame. A finished animation starts over. If no animation
+    /// is registered as `name`, the sprite shows its own image.
+    pub fn play_animation<S: Into<String>>(&mut self, name: S) {
+        let name = name.into();
+        match self.animation.as_mut() {
+            Some(animation) if animation.name == name && !animation.finished => {
+                animation.paused = false;
+            }
+            _ => self.animation = Some(SpriteAnimation::new(name)),
+        }
+    }
+
+    /// Stop the current animation (if any), going back to the image at `filepath`
+    pub fn stop_animation(&mut self) {
+        self.animation = None;
+    }
+
+    /// Pause the current animation (if any) on the frame it is showing
+    pub fn pause_animation(&mut self) {
+        if let Some(animation) = self.animation.as_mut() {
+            animation.paused = true;
+        }
+    }
+
+    /// Resume the current animation (if any) after it was paused
+    pub fn resume_animation(&mut self) {
+        if let Some(animation) = self.animation.as_mut() {
+            animation.paused = false;
+        }
+    }
+
+    /// Whether the current animation is an [`AnimationMode::Once`] animation that has finished
+    /// playing. `false` if there is no current animation.
+    ///
+    /// [`AnimationMode::Once`]: crate::animation::AnimationMode::Once
+    pub fn animation_finished(&self) -> bool {
+        self.animation
+            .as_ref()
+            .map_or(false, |animation| animation.finished)
+    }
+
+    /// If `shape` was changed, generate a collider that matches the new shape. Otherwise, if
+    /// `filepath` was changed (or the sprite stopped being a shape), load the collider that goes
+    /// with the image. The engine calls this for you.
+    #[doc(hidden)]
+    pub fn refresh_collider(&mut self) {
+        if let Some(shape) = self.shape.as_ref() {
+            if self.collider_shape.as_ref() != Some(shape) {
+                self.collider = shape.collider();
+                self.convex_decomposition = ConvexDecomposition::new(&self.collider);
+                self.collider_shape = Some(shape.clone());
+            }
+            return;
+        }
+        if self.collider_shape.take().is_none()
+            && self.collider_filepath == self.filepath.with_extension("collider")
+        {
+            return;
+        }
+        let (collider_filepath, collider) = collider_for(&self.filepath);
+        self.collider_filepath = collider_filepath;
+        self.convex_decomposition = ConvexDecomposition::new(&collider);
+        self.collider = collider;
+    }
+
+    /// The color of the sprite's material: the tint, with the alpha applied
+    #[doc(hidden)]
+    pub fn material_color(&self) -> Color {
+        let mut color = self.tint;
+        color.set_a(self.tint.a() * self.alpha);
+        color
+    }
+
+    // -1.0 on each axis the sprite is flipped along, 1.0 otherwise
+    pub(crate) fn mirror(&self) -> Vec2 {
+        Vec2::new(
+            if self.flip_x { -1.0 } else { 1.0 },
+            if self.flip_y { -1.0 } else { 1.0 },
+        )
+    }
+
+    // What to divide a point by to undo the sprite's stretching and flipping. A sprite stretched
+    // to nothing along an axis has squashed all its points together along it, so there is no
+    // undoing that. That axis is left alone rather than dividing by zero.
+    fn unstretch(&self) -> Vec2 {
+        let stretch = self.mirror() * self.stretch;
+        Vec2::new(
+            if stretch.x == 0.0 { 1.0 } else { stretch.x },
+            if stretch.y == 0.0 { 1.0 } else { stretch.y },
+        )
+    }
+
+    /// Where the sprite is in world space. Sprites without a parent are wherever their own fields
+    /// say, even if they were changed since the engine last updated the `world_*` fields.
+    pub fn world_transform(&self) -> WorldTransform {
+        if self.parent.is_some() {
+            WorldTransform {
+                translation: self.world_translation,
+                rotation: self.world_rotation,
+                scale: self.world_scale,
+            }
+        } else {
+            WorldTransform {
+                translation: self.translation,
+                rotation: self.rotation,
+                scale: self.scale,
+            }
+        }
+    }
+
+    /// Set the `world_*` fields
+    #[doc(hidden)]
+    pub fn set_world_transform(&mut self, world_transform: WorldTransform) {
+        self.world_translation = world_transform.translation;
+        self.world_rotation = world_transform.rotation;
+        self.world_scale = world_transform.scale;
+    }
+
     /// Do the math to translate from Rusty Engine translation+rotation+scale to Bevy's Transform
     #[doc(hidden)]
     pub fn bevy_transform(&self) -> Transform {
-        let mut transform = Transform::from_translation(self.translation.extend(self.layer));
-        transform.rotation = Quat::from_axis_angle(Vec3::Z, self.rotation);
-        transform.scale = Vec3::splat(self.scale);
+        let world = self.world_transform();
+        let mut transform = Transform::from_translation(world.translation.extend(self.layer));
+        transform.rotation = Quat::from_axis_angle(Vec3::Z, world.rotation);
+        transform.scale = (self.stretch * world.scale).extend(world.scale);
         transform
     }
 
@@ -125,33 +417,41 @@ impl Sprite {
             }
         }
     }
-    /// Add a collider point. `p` is a `Vec2` in worldspace (usually the mouse coordinate). See the
-    /// `collider_creator` example.
+    /// Add a collider point. `p` is a `Vec2` in worldspace (usually the mouse coordinate). If the
+    /// collider isn't a polygon, it is replaced with a new polygon. See the `collider_creator`
+    /// example.
     pub fn add_collider_point(&mut self, mut p: Vec2) {
-        // If there isn't a collider, we better switch to one
-        if self.collider == Collider::NoCollider {
+        let world = self.world_transform();
+        let unstretch = self.unstretch();
+        // If there isn't a polygon collider, we better switch to one
+        if !self.collider.is_poly() {
             self.collider = Collider::Poly(Vec::new());
         }
         // Add the current point to the collider
         if let Collider::Poly(points) = &mut self.collider {
             // untranslate (make p relative to the sprite's position)
-            p -= self.translation;
+            p -= world.translation;
             // unscale (make p the same scale as the sprite)
-            p *= 1.0 / self.scale;
+            p *= 1.0 / world.scale;
             // unrotate (make p the same rotation as the sprite)
             let mut p2 = Vec2::ZERO;
-            let sin = (-self.rotation).sin();
-            let cos = (-self.rotation).cos();
+            let sin = (-world.rotation).sin();
+            let cos = (-world.rotation).cos();
             p2.x = p.x * cos - p.y * sin;
             p2.y = p.x * sin + p.y * cos;
+            // unstretch and unflip (make p the same shape and orientation as the image file)
+            p2 /= unstretch;
             points.push(p2);
         }
+        self.convex_decomposition = ConvexDecomposition::new(&self.collider);
     }
     /// Change the last collider point. `p` is a `Vec2` in worldspace (usually the mouse
     /// coordinate). See the `collider_creator` example.
     pub fn change_last_collider_point(&mut self, mut p: Vec2) {
-        // If there isn't a collider, create one with a "last point" to change
-        if self.collider == Collider::NoCollider {
+        let world = self.world_transform();
+        let unstretch = self.unstretch();
+        // If there isn't a polygon collider, create one with a "last point" to change
+        if !self.collider.is_poly() {
             self.collider = Collider::Poly(vec![Vec2::ZERO]);
         }
         // Add the current point to the collider
@@ -161,18 +461,131 @@ impl Sprite {
                 points.push(Vec2::ZERO);
             }
             // untranslate (make p relative to the sprite's origin instead of the world's origin)
-            p -= self.translation;
+            p -= world.translation;
             // unscale (make p the same scale as the sprite)
-            p *= 1.0 / self.scale;
+            p *= 1.0 / world.scale;
             // unrotate (make p the same rotation as the sprite)
             let length = points.len();
             let p2 = points.get_mut(length - 1).unwrap(); // mutable reference to "last point"
-            let sin = (-self.rotation).sin();
-            let cos = (-self.rotation).cos();
+            let sin = (-world.rotation).sin();
+            let cos = (-world.rotation).cos();
             p2.x = p.x * cos - p.y * sin;
             p2.y = p.x * sin + p.y * cos;
+            // unstretch and unflip (make p the same shape and orientation as the image file)
+            *p2 /= unstretch;
+        }
+        self.convex_decomposition = ConvexDecomposition::new(&self.collider);
+    }
+}
+
+/// What a sprite's texture is made from
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpriteImage {
+    /// An image file, relative to `assets/sprite`
+    File(PathBuf),
+    /// A shape, drawn by the engine
+    Shape(SpriteShape),
+}
+
+impl SpriteImage {
+    /// Load the image file, or get the texture of the shape (drawing it if needed)
+    pub fn texture(
+        &self,
+        asset_server: &AssetServer,
+        textures: &mut Assets<Texture>,
+        shape_textures: &mut ShapeTextures,
+    ) -> Handle<Texture> {
+        match self {
+            SpriteImage::File(filepath) => {
+                asset_server.load(PathBuf::from("sprite").join(filepath))
+            }
+            SpriteImage::Shape(shape) => shape_textures.get_or_add(shape, textures),
+        }
+    }
+}
+
+/// A translation, rotation, and scale in world space, like a sprite's `world_translation`,
+/// `world_rotation`, and `world_scale`. See [`Sprite::world_transform`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldTransform {
+    /// Where in the world, in pixels
+    pub translation: Vec2,
+    /// Counterclockwise rotation, in radians
+    pub rotation: f32,
+    /// 1.0 is the normal 100%
+    pub scale: f32,
+}
+
+impl WorldTransform {
+    /// Place something whose translation, rotation, and scale are relative to this transform into
+    /// world space
+    pub fn child(&self, translation: Vec2, rotation: f32, scale: f32) -> Self {
+        Self {
+            translation: self.translation + rotate(translation * self.scale, self.rotation),
+            rotation: self.rotation + rotation,
+            scale: self.scale * scale,
+        }
+    }
+}
+
+/// The world transforms of all the sprites, by label. A sprite whose parent doesn't exist is
+/// placed as if it had no parent.
+#[doc(hidden)]
+pub fn world_transforms<'a>(
+    sprites: impl Iterator<Item = &'a Sprite>,
+) -> HashMap<String, WorldTransform> {
+    let sprites: HashMap<&str, &Sprite> = sprites
+        .map(|sprite| (sprite.label.as_str(), sprite))
+        .collect();
+    let mut world_transforms = HashMap::default();
+    for label in sprites.keys() {
+        resolve_world_transform(label, &sprites, &mut world_transforms, 0);
+    }
+    world_transforms
+}
+
+// Work out a sprite's world transform, working out its ancestors' first
+fn resolve_world_transform(
+    label: &str,
+    sprites: &HashMap<&str, &Sprite>,
+    world_transforms: &mut HashMap<String, WorldTransform>,
+    depth: usize,
+) -> WorldTransform {
+    if let Some(world_transform) = world_transforms.get(label) {
+        return *world_transform;
+    }
+    let sprite = sprites[label];
+    // If we have gone deeper than there are sprites, the parents form a loop. Break it here.
+    let parent = sprite
+        .parent
+        .as_deref()
+        .filter(|parent| sprites.contains_key(parent) && depth < sprites.len());
+    let world_transform = match parent {
+        Some(parent) => resolve_world_transform(parent, sprites, world_transforms, depth + 1)
+            .child(sprite.translation, sprite.rotation, sprite.scale),
+        None => WorldTransform {
+            translation: sprite.translation,
+            rotation: sprite.rotation,
+            scale: sprite.scale,
+        },
+    };
+    world_transforms.insert(label.to_string(), world_transform);
+    world_transform
+}
+
+/// Update the `world_*` fields of all the sprites. The world transforms are returned, too.
+#[doc(hidden)]
+pub fn update_world_transforms(
+    sprites: &mut HashMap<String, Sprite>,
+) -> HashMap<String, WorldTransform> {
+    let world_transforms = world_transforms(sprites.values());
+    for sprite in sprites.values_mut() {
+        if let Some(world_transform) = world_transforms.get(&sprite.label) {
+            sprite.set_world_transform(*world_transform);
         }
     }
+    world_transforms
 }
 
 use std::{
@@ -1,4 +1,32 @@
-use bevy::prelude::{Quat, Transform, Vec2, Vec3};
+use crate::{camera::ScreenAnchor, sprite::WorldTransform};
+use bevy::{
+    app::CoreStage,
+    asset::AddAsset,
+    math::Size,
+    prelude::{
+        AppBuilder, AssetServer, Assets, Changed, Color, Draw, Entity, GlobalTransform, IntoSystem,
+        Local, Mat4, Mesh, Msaa, Plugin, Quat, Query, QuerySet, Res, ResMut, Text as BevyText,
+        TextAlignment, TextSection as BevyTextSection, TextStyle, Texture, Transform, Vec2, Vec3,
+        Visible, With, Without,
+    },
+    render::{
+        draw::{DrawContext, DrawError, OutsideFrustum},
+        mesh,
+        pipeline::{IndexFormat, PipelineSpecialization, VertexBufferLayout},
+        render_graph::base::MainPass,
+        renderer::{BindGroup, RenderResourceBindings, RenderResourceId},
+        RenderStage,
+    },
+    sprite::{TextureAtlas, TextureAtlasSprite, QUAD_HANDLE, SPRITE_SHEET_PIPELINE_HANDLE},
+    text::{
+        scale_value, DefaultTextPipeline, Font, FontAtlasSet, FontLoader, PositionedGlyph,
+        Text2dSize, TextError,
+    },
+    window::Windows,
+};
+
+// Re-export Bevy's alignment types to use
+pub use bevy::prelude::{HorizontalAlign, VerticalAlign};
 
 /// Default depth of the text, positioned so it will be on top of other default layers. Depth
 /// can range from `0.0` (back) to `999.0` (front)
@@ -6,6 +34,22 @@ pub const TEXT_DEFAULT_LAYER: f32 = 900.0;
 /// Default font size for a text.
 pub const TEXT_DEFAULT_FONT_SIZE: f32 = 30.0;
 
+/// Takes the place of Bevy's own `TextPlugin`, which only draws text at its translation and never
+/// wraps it. Ours draws text with its whole transform, so rotation and scale work too, and lays
+/// text out within its `max_width` and `max_height`.
+pub struct TextPlugin;
+
+impl Plugin for TextPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<Font>()
+            .add_asset::<FontAtlasSet>()
+            .init_asset_loader::<FontLoader>()
+            .insert_resource(DefaultTextPipeline::default())
+            .add_system_to_stage(CoreStage::PostUpdate, layout_texts.system())
+            .add_system_to_stage(RenderStage::Draw, draw_texts.system());
+    }
+}
+
 /// A [`Text`] is a bit of text that exists on the screen.
 #[derive(Clone, Debug)]
 pub struct Text {
@@ -13,7 +57,7 @@ pub struct Text {
     /// label you use to retrieve and modify your text from the
     /// [`EngineState::texts`](crate::prelude::EngineState::texts) HashMap.
     pub label: String,
-    /// SYNCED: The actual text value you want to display.
+    /// SYNCED: The actual text value you want to display. Ignored if there are any `sections`.
     pub value: String,
     /// CREATION: The font to use when creating this text. Should be a file name of an .otf or
     /// .ttf font located within the assets/font folder. Defaults to "FiraSans-Bold.ttf" (included
@@ -23,19 +67,59 @@ pub struct Text {
     /// the sprites we generate for them get slower to create. Very large sizes will crash. The
     /// default font size is `30.0`.
     pub font_size: f32,
+    /// SYNCED: The color of the text. Defaults to white.
+    pub color: Color,
+    /// SYNCED: Which part of the text is placed at `translation`, horizontally. For example,
+    /// `HorizontalAlign::Left` puts the left edge of the text at `translation`, so the text extends
+    /// to the right. Lines of multi-line text are lined up with each other the same way. Defaults
+    /// to `HorizontalAlign::Center`.
+    pub horizontal_align: HorizontalAlign,
+    /// SYNCED: Which part of the text is placed at `translation`, vertically. For example,
+    /// `VerticalAlign::Top` puts the top edge of the text at `translation`, so the text extends
+    /// downward. Defaults to `VerticalAlign::Center`.
+    pub vertical_align: VerticalAlign,
+    /// SYNCED: Pieces of text with their own fonts, font sizes, and colors, shown one after another
+    /// in place of `value`. Handy for things like a score where only the number is highlighted.
+    /// See [`TextSection`].
+    pub sections: Vec<TextSection>,
+    /// SYNCED: If set, lines that would be wider than this many pixels are wrapped onto the next
+    /// line, between words. A single word that is too wide is left as it is. Defaults to `None`
+    /// (lines are only broken at newlines).
+    pub max_width: Option<f32>,
+    /// SYNCED: If set, lines that would take the text past this many pixels tall are left out.
+    /// Together with `max_width`, this makes a box that the text has to fit in. Defaults to `None`.
+    pub max_height: Option<f32>,
+    /// READONLY: The size of the text in pixels, as it was laid out with its fonts, font sizes, and
+    /// wrapping. Handy for sizing a background behind the text, or for lining texts up. This is the
+    /// size before `scale` is applied. It is `Vec2::ZERO` until the text has been laid out (at the
+    /// end of the frame it was added in, or later if its font is still loading), and after a change
+    /// it is updated by the next frame.
+    pub size: Vec2,
     /// SYNCED: Where you are in 2D game space. Positive x is right. Positive y is up. (0.0, 0.0) is the
-    /// center of the screen.
+    /// center of the screen. If the text has a `parent`, this is relative to the parent instead.
     pub translation: Vec2,
     /// SYNCED: Depth of the text. 0.0 (back) to 999.0 (front)  Defaults to [`TEXT_DEFAULT_LAYER`]
     pub layer: f32,
     /// SYNCED: Direction you face in radians. Defaults to [`RIGHT`](crate::RIGHT). See also
     /// the [direction constants](https://docs.rs/rusty_engine/latest/rusty_engine/#constants).
-    /// WARNING: This field will not affect text rotation until Bevy 0.6 is released and Rusty
-    /// Engine is updated to use it.
+    /// The text turns around the point it is aligned to (see `horizontal_align` and
+    /// `vertical_align`).
     pub rotation: f32,
-    /// SYNCED: 1.0 is the normal 100%. WARNING: This field will not affect text scale
-    /// until Bevy 0.6 is released and Rusty Engine is updated to use it.
+    /// SYNCED: 1.0 is the normal 100%. Text is drawn at `font_size` and then scaled, so text
+    /// that is scaled up a lot looks pixellated. For text that stays big, use a bigger
+    /// `font_size` instead.
     pub scale: f32,
+    /// SYNCED: The label of a sprite to attach this text to, if any. Attached text follows the
+    /// sprite around: its `translation`, `rotation`, and `scale` are relative to the sprite's. When
+    /// the sprite is removed, so is the text. If there is no sprite with that label, the text is
+    /// placed as if it had no parent. Handy for things like name tags over players.
+    pub parent: Option<String>,
+    /// SYNCED: Set this to put the text on the UI layer, where it stays put on the screen even as
+    /// the camera moves or rotates. `translation` is then an offset in pixels from this point on
+    /// the screen, which keeps up with the window being resized. Great for scores and health
+    /// displays. Text attached to a sprite on the UI layer is on the UI layer too (and its own
+    /// `screen_anchor` is ignored).
+    pub screen_anchor: Option<ScreenAnchor>,
 }
 
 impl Default for Text {
@@ -45,20 +129,415 @@ impl Default for Text {
             value: String::default(),
             font: "FiraSans-Bold.ttf".to_string(),
             font_size: TEXT_DEFAULT_FONT_SIZE,
+            color: Color::WHITE,
+            horizontal_align: HorizontalAlign::Center,
+            vertical_align: VerticalAlign::Center,
+            sections: Vec::new(),
+            max_width: None,
+            max_height: None,
+            size: Vec2::ZERO,
             translation: Vec2::default(),
             layer: TEXT_DEFAULT_LAYER,
             rotation: f32::default(),
             scale: 1.0,
+            parent: None,
+            screen_anchor: None,
         }
     }
 }
 
 impl Text {
+    /// The Bevy text to display, with the font, font size, and color of each section filled in
     #[doc(hidden)]
-    pub fn bevy_transform(&self) -> Transform {
-        let mut transform = Transform::from_translation(self.translation.extend(self.layer));
-        transform.rotation = Quat::from_axis_angle(Vec3::Z, self.rotation);
-        transform.scale = Vec3::splat(self.scale);
+    pub fn bevy_text(&self, asset_server: &AssetServer) -> BevyText {
+        let style = |font: &str, font_size: f32, color: Color| TextStyle {
+            font: asset_server.load(format!("font/{}", font).as_str()),
+            font_size,
+            color,
+        };
+        let sections = if self.sections.is_empty() {
+            vec![BevyTextSection {
+                value: self.value.clone(),
+                style: style(&self.font, self.font_size, self.color),
+            }]
+        } else {
+            self.sections
+                .iter()
+                .map(|section| BevyTextSection {
+                    value: section.value.clone(),
+                    style: style(
+                        section.font.as_deref().unwrap_or(&self.font),
+                        section.font_size.unwrap_or(self.font_size),
+                        section.color.unwrap_or(self.color),
+                    ),
+                })
+                .collect()
+        };
+        BevyText {
+            sections,
+            alignment: TextAlignment {
+                horizontal: self.horizontal_align,
+                vertical: self.vertical_align,
+            },
+        }
+    }
+
+    /// `parent` is the world transform of the parent sprite, if the text has one
+    #[doc(hidden)]
+    pub fn bevy_transform(&self, parent: Option<&WorldTransform>) -> Transform {
+        let world = match parent {
+            Some(parent) => parent.child(self.translation, self.rotation, self.scale),
+            None => WorldTransform {
+                translation: self.translation,
+                rotation: self.rotation,
+                scale: self.scale,
+            },
+        };
+        let mut transform = Transform::from_translation(world.translation.extend(self.layer));
+        transform.rotation = Quat::from_axis_angle(Vec3::Z, world.rotation);
+        transform.scale = Vec3::splat(world.scale);
         transform
     }
 }
+
+/// One piece of a [`Text`] with its own style. Any part of the style that isn't set is taken from
+/// the [`Text`] that the section is in.
+///
+/// ```rust
+/// # use rusty_engine::prelude::*;
+/// # let mut text = Text::default();
+/// text.sections = vec![
+///     TextSection::new("Score: "),
+///     TextSection::new("42").with_color(Color::YELLOW),
+/// ];
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextSection {
+    /// The text to display
+    pub value: String,
+    /// The font to use, just like [`Text::font`]. `None` uses the text's font.
+    pub font: Option<String>,
+    /// The font size to use. `None` uses the text's font size.
+    pub font_size: Option<f32>,
+    /// The color to use. `None` uses the text's color.
+    pub color: Option<Color>,
+}
+
+impl TextSection {
+    /// A section of text styled just like the [`Text`] it is in
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        Self {
+            value: value.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Use a different font for this section. Should be a file name of an .otf or .ttf font located
+    /// within the assets/font folder.
+    pub fn with_font<S: Into<String>>(mut self, font: S) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+
+    /// Use a different font size for this section
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    /// Use a different color for this section
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// Returns true if two Bevy texts look the same. Bevy lays text out again whenever it changes, so
+/// we only want to change it when it actually needs to be.
+#[doc(hidden)]
+pub fn bevy_text_eq(a: &BevyText, b: &BevyText) -> bool {
+    #[allow(clippy::float_cmp)]
+    let section_eq = |a: &BevyTextSection, b: &BevyTextSection| {
+        a.value == b.value
+            && a.style.font == b.style.font
+            && a.style.font_size == b.style.font_size
+            && a.style.color == b.style.color
+    };
+    a.alignment.horizontal == b.alignment.horizontal
+        && a.alignment.vertical == b.alignment.vertical
+        && a.sections.len() == b.sections.len()
+        && a.sections
+            .iter()
+            .zip(&b.sections)
+            .all(|(a, b)| section_eq(a, b))
+}
+
+// system - lay out the texts that changed, within their bounds, and record their sizes. Based on
+// Bevy's `text2d_system`, which never wraps text.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn layout_texts(
+    mut queued_texts: Local<Vec<Entity>>,
+    mut textures: ResMut<Assets<Texture>>,
+    fonts: Res<Assets<Font>>,
+    windows: Res<Windows>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut font_atlas_set_storage: ResMut<Assets<FontAtlasSet>>,
+    mut text_pipeline: ResMut<DefaultTextPipeline>,
+    mut text_queries: QuerySet<(
+        Query<Entity, (With<MainPass>, Changed<BevyText>)>,
+        Query<(&BevyText, &mut Text, &mut Text2dSize), With<MainPass>>,
+    )>,
+) {
+    for entity in text_queries.q0().iter() {
+        queued_texts.push(entity);
+    }
+    if queued_texts.is_empty() {
+        return;
+    }
+    let scale_factor = windows
+        .get_primary()
+        .map_or(1.0, |window| window.scale_factor());
+
+    // Texts whose fonts haven't loaded yet stay in the queue to try again next frame
+    let mut still_queued = Vec::new();
+    let query = text_queries.q1_mut();
+    for entity in queued_texts.drain(..) {
+        let (bevy_text, mut text, mut size) = match query.get_mut(entity) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+        // The layout is done in physical pixels, so the bounds need to be too
+        let bounds = Size::new(
+            text.max_width
+                .map_or(f32::MAX, |width| scale_value(width, scale_factor)),
+            text.max_height
+                .map_or(f32::MAX, |height| scale_value(height, scale_factor)),
+        );
+        match text_pipeline.queue_text(
+            entity,
+            &fonts,
+            &bevy_text.sections,
+            scale_factor,
+            bevy_text.alignment,
+            bounds,
+            &mut *font_atlas_set_storage,
+            &mut *texture_atlases,
+            &mut *textures,
+        ) {
+            Ok(()) => {
+                // Unwrap: Can't crash because the glyphs were just laid out
+                let layout_size = text_pipeline.get_glyphs(&entity).unwrap().size;
+                size.size = Size::new(
+                    scale_value(layout_size.width, 1.0 / scale_factor),
+                    scale_value(layout_size.height, 1.0 / scale_factor),
+                );
+                text.size = Vec2::new(size.size.width, size.size.height);
+            }
+            Err(TextError::NoSuchFont) => still_queued.push(entity),
+            Err(e) => eprintln!("failed to lay out text {}: {}", text.label, e),
+        }
+    }
+    *queued_texts = still_queued;
+}
+
+// system - draw each text with its whole transform. Based on Bevy's `draw_text2d_system`, which
+// only uses the translation.
+#[allow(clippy::type_complexity)]
+fn draw_texts(
+    mut context: DrawContext,
+    msaa: Res<Msaa>,
+    meshes: Res<Assets<Mesh>>,
+    windows: Res<Windows>,
+    mut render_resource_bindings: ResMut<RenderResourceBindings>,
+    text_pipeline: Res<DefaultTextPipeline>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Draw,
+            &Visible,
+            &BevyText,
+            &GlobalTransform,
+            &Text2dSize,
+        ),
+        (With<MainPass>, Without<OutsideFrustum>),
+    >,
+) {
+    let font_quad_vertex_layout = match meshes.get(&QUAD_HANDLE) {
+        Some(font_quad) => font_quad.get_vertex_buffer_layout(),
+        None => return,
+    };
+    let scale_factor = windows
+        .get_primary()
+        .map_or(1.0, |window| window.scale_factor() as f32);
+
+    for (entity, mut draw, visible, text, global_transform, size) in query.iter_mut() {
+        if !visible.is_visible {
+            continue;
+        }
+        let text_glyphs = match text_pipeline.get_glyphs(&entity) {
+            Some(text_glyphs) => text_glyphs,
+            None => continue,
+        };
+        // The glyphs are laid out in a box that starts at the origin and extends up and to the
+        // right. Move the box so the aligned point is at the origin, then transform it.
+        let (width, height) = (size.size.width, size.size.height);
+        let offset = Vec2::new(
+            match text.alignment.horizontal {
+                HorizontalAlign::Left => 0.0,
+                HorizontalAlign::Center => -width * 0.5,
+                HorizontalAlign::Right => -width,
+            },
+            match text.alignment.vertical {
+                VerticalAlign::Top => -height,
+                VerticalAlign::Center => -height * 0.5,
+                VerticalAlign::Bottom => 0.0,
+            },
+        );
+        let transform =
+            global_transform.compute_matrix() * Mat4::from_translation(offset.extend(0.0));
+        if let Err(e) = draw_glyphs(
+            &mut draw,
+            &mut context,
+            &mut render_resource_bindings,
+            &msaa,
+            &font_quad_vertex_layout,
+            transform,
+            scale_factor,
+            text,
+            &text_glyphs.glyphs,
+        ) {
+            eprintln!("failed to draw text: {}", e);
+        }
+    }
+}
+
+// Draw each glyph as a sprite from its font atlas. Glyphs are in physical pixels, so they are
+// scaled down to logical pixels before `transform` (which places the text) is applied.
+#[allow(clippy::too_many_arguments)]
+fn draw_glyphs(
+    draw: &mut Draw,
+    context: &mut DrawContext,
+    render_resource_bindings: &mut RenderResourceBindings,
+    msaa: &Msaa,
+    font_quad_vertex_layout: &VertexBufferLayout,
+    transform: Mat4,
+    scale_factor: f32,
+    text: &BevyText,
+    glyphs: &[PositionedGlyph],
+) -> Result<(), DrawError> {
+    context.set_pipeline(
+        draw,
+        &SPRITE_SHEET_PIPELINE_HANDLE.typed(),
+        &PipelineSpecialization {
+            sample_count: msaa.samples,
+            vertex_buffer_layout: font_quad_vertex_layout.clone(),
+            ..Default::default()
+        },
+    )?;
+
+    let render_resource_context = &**context.render_resource_context;
+    let quad = QUAD_HANDLE.typed::<Mesh>();
+    if let Some(RenderResourceId::Buffer(vertex_buffer)) =
+        render_resource_context.get_asset_resource(&quad, mesh::VERTEX_ATTRIBUTE_BUFFER_ID)
+    {
+        draw.set_vertex_buffer(0, vertex_buffer, 0);
+    }
+    let mut indices = 0..0;
+    if let Some(RenderResourceId::Buffer(index_buffer)) =
+        render_resource_context.get_asset_resource(&quad, mesh::INDEX_BUFFER_ASSET_INDEX)
+    {
+        draw.set_index_buffer(index_buffer, 0, IndexFormat::Uint32);
+        if let Some(buffer_info) = render_resource_context.get_buffer_info(index_buffer) {
+            indices = 0..(buffer_info.size / 4) as u32;
+        }
+    }
+
+    context.set_bind_groups_from_bindings(draw, &mut [render_resource_bindings])?;
+
+    let to_logical = Mat4::from_scale(Vec3::splat(1.0 / scale_factor));
+    for glyph in glyphs {
+        context.set_asset_bind_groups(draw, &glyph.atlas_info.texture_atlas)?;
+        let sprite = TextureAtlasSprite {
+            index: glyph.atlas_info.glyph_index,
+            color: text.sections[glyph.section_index].style.color,
+            flip_x: false,
+            flip_y: false,
+        };
+        let glyph_transform =
+            transform * to_logical * Mat4::from_translation(glyph.position.extend(0.0));
+        let sprite_bind_group = BindGroup::build()
+            .add_binding(0, context.get_uniform_buffer(&glyph_transform)?)
+            .add_binding(1, context.get_uniform_buffer(&sprite)?)
+            .finish();
+        context.create_bind_group_resource(2, &sprite_bind_group)?;
+        draw.set_bind_group(2, &sprite_bind_group);
+        draw.draw_indexed(indices.clone(), 0, 0..1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        asset::{Handle, HandleUntyped},
+        reflect::TypeUuid,
+    };
+
+    fn font(id: u64) -> Handle<Font> {
+        HandleUntyped::weak_from_u64(Font::TYPE_UUID, id).typed()
+    }
+
+    fn bevy_text(sections: &[(&str, u64, f32, Color)]) -> BevyText {
+        BevyText {
+            sections: sections
+                .iter()
+                .map(|&(value, font_id, font_size, color)| BevyTextSection {
+                    value: value.to_string(),
+                    style: TextStyle {
+                        font: font(font_id),
+                        font_size,
+                        color,
+                    },
+                })
+                .collect(),
+            alignment: TextAlignment::default(),
+        }
+    }
+
+    #[test]
+    fn identical_texts_are_equal() {
+        let sections = [
+            ("Score: ", 1, 30.0, Color::WHITE),
+            ("10", 2, 40.0, Color::RED),
+        ];
+        assert!(bevy_text_eq(&bevy_text(&sections), &bevy_text(&sections)));
+    }
+
+    #[test]
+    fn any_visible_difference_makes_texts_unequal() {
+        let text = bevy_text(&[("Score", 1, 30.0, Color::WHITE)]);
+        let different = [
+            bevy_text(&[("Scare", 1, 30.0, Color::WHITE)]),
+            bevy_text(&[("Score", 2, 30.0, Color::WHITE)]),
+            bevy_text(&[("Score", 1, 31.0, Color::WHITE)]),
+            bevy_text(&[("Score", 1, 30.0, Color::BLACK)]),
+            bevy_text(&[
+                ("Score", 1, 30.0, Color::WHITE),
+                ("!", 1, 30.0, Color::WHITE),
+            ]),
+            bevy_text(&[]),
+        ];
+        for other in different.iter() {
+            assert!(!bevy_text_eq(&text, other));
+            assert!(!bevy_text_eq(other, &text));
+        }
+
+        let mut aligned = bevy_text(&[("Score", 1, 30.0, Color::WHITE)]);
+        aligned.alignment.horizontal = HorizontalAlign::Right;
+        assert!(!bevy_text_eq(&text, &aligned));
+        let mut aligned = bevy_text(&[("Score", 1, 30.0, Color::WHITE)]);
+        aligned.alignment.vertical = VerticalAlign::Bottom;
+        assert!(!bevy_text_eq(&text, &aligned));
+    }
+}
@@ -0,0 +1,595 @@
+//! Grid-based levels, like mazes and platformer stages, made out of tiles.
+//!
+//! A [`Tilemap`] is a grid of cells, each of which shows one of the tiles from a [`Tileset`] image
+//! (or nothing). The whole tilemap is drawn at once, so it stays fast no matter how many cells it
+//! has. Some tiles (like walls) can be marked as solid, and sprites then collide with them.
+//!
+//! Tilemaps are added with
+//! [`EngineState::add_tilemap`](crate::prelude::EngineState::add_tilemap). They can be built in
+//! code, from a grid of characters with [`Tilemap::from_text`], or loaded from a
+//! [Rusty Object Notation (RON)](https://github.com/ron-rs/ron) file with [`Tilemap::from_file`]
+//! that looks like this:
+//!
+//! ```text
+//! (
+//!     tileset: (filepath: "rolling/tiles.png", columns: 4, rows: 1, solid: [1]),
+//!     tile_size: (64.0, 64.0),
+//!     legend: {'.': 0, '#': 1, 'o': 2},
+//!     grid: [
+//!         "#####",
+//!         "#..o#",
+//!         "#####",
+//!     ],
+//! )
+//! ```
+use crate::prelude::EngineState;
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, Mesh},
+        pipeline::PrimitiveTopology,
+    },
+    sprite::SpriteResizeMode,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+pub struct TilemapPlugin;
+
+impl Plugin for TilemapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(
+            sync_tilemaps
+                .system()
+                .label("sync_tilemaps")
+                .after("game_logic_sync"),
+        );
+    }
+}
+
+/// An image made up of a grid of equally sized tiles. The tiles are numbered from left to right,
+/// then top to bottom, starting at `0`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Tileset {
+    /// The image, relative to `assets/sprite` (just like [`Sprite::filepath`])
+    ///
+    /// [`Sprite::filepath`]: crate::prelude::Sprite::filepath
+    pub filepath: PathBuf,
+    /// How many tiles across the image is
+    pub columns: usize,
+    /// How many tiles down the image is
+    pub rows: usize,
+    /// The numbers of the tiles that are solid, like walls. Sprites with `collision` enabled
+    /// collide with the cells of a tilemap that show a solid tile.
+    #[serde(default)]
+    pub solid: HashSet<usize>,
+}
+
+impl Tileset {
+    /// A tileset image (relative to `assets/sprite`) that is `columns` tiles across and `rows`
+    /// tiles down, with no solid tiles
+    pub fn new<P: Into<PathBuf>>(filepath: P, columns: usize, rows: usize) -> Self {
+        Self {
+            filepath: filepath.into(),
+            columns,
+            rows,
+            solid: HashSet::new(),
+        }
+    }
+
+    /// Mark some tiles as solid. Returns the tileset, so you can chain calls.
+    pub fn with_solid(mut self, tiles: &[usize]) -> Self {
+        self.solid.extend(tiles);
+        self
+    }
+}
+
+// The layout of a tilemap file
+#[derive(Deserialize)]
+struct TilemapFile {
+    tileset: Tileset,
+    tile_size: Vec2,
+    legend: std::collections::HashMap<char, usize>,
+    grid: Vec<String>,
+}
+
+/// A grid of tiles. See the [module-level docs](crate::tilemap).
+///
+/// Cells are found by their column (counting from the left) and row (counting from the top),
+/// starting at `0`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tilemap {
+    /// READONLY: A way to identify the tilemap. Collision events between a sprite and the
+    /// tilemap's solid tiles use this label.
+    pub label: String,
+    /// SYNCED: The tiles to show in the cells
+    pub tileset: Tileset,
+    /// SYNCED: The size of each cell, in pixels. Tiles are stretched to fill their cells.
+    pub tile_size: Vec2,
+    /// SYNCED: Where the center of the tilemap is in 2D game space
+    pub translation: Vec2,
+    /// SYNCED: Depth of the tilemap. 0.0 (back) to 999.0 (front). Defaults to `0.0`, behind
+    /// everything else.
+    pub layer: f32,
+    /// SYNCED: Whether sprites collide with the solid tiles. Defaults to `true`.
+    pub collision: bool,
+    /// SYNCED: The collision group of the solid tiles, for sprites' collision masks (see
+    /// [`Sprite::collision_mask`]). Defaults to an empty string (no particular group).
+    ///
+    /// [`Sprite::collision_mask`]: crate::prelude::Sprite::collision_mask
+    pub collision_group: String,
+    columns: usize,
+    rows: usize,
+    // The tile in each cell, row by row
+    tiles: Vec<Option<usize>>,
+}
+
+impl Tilemap {
+    /// An empty tilemap, `columns` cells across and `rows` cells down. `label` should be a unique
+    /// string.
+    pub fn new<S: Into<String>>(
+        label: S,
+        tileset: Tileset,
+        tile_size: Vec2,
+        columns: usize,
+        rows: usize,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            tileset,
+            tile_size,
+            translation: Vec2::ZERO,
+            layer: 0.0,
+            collision: true,
+            collision_group: String::new(),
+            columns,
+            rows,
+            tiles: vec![None; columns * rows],
+        }
+    }
+
+    /// A tilemap built from a grid of characters, one line per row. `legend` says which tile each
+    /// character stands for. Characters that aren't in the legend (like spaces) leave their cells
+    /// empty. The tilemap is as wide as the longest line.
+    pub fn from_text<S: Into<String>>(
+        label: S,
+        tileset: Tileset,
+        tile_size: Vec2,
+        text: &str,
+        legend: &[(char, usize)],
+    ) -> Self {
+        let lines: Vec<&str> = text
+            .trim_matches(|c| c == '\n' || c == '\r')
+            .lines()
+            .collect();
+        Self::from_lines(label, tileset, tile_size, &lines, legend)
+    }
+
+    fn from_lines<S: Into<String>, L: AsRef<str>>(
+        label: S,
+        tileset: Tileset,
+        tile_size: Vec2,
+        lines: &[L],
+        legend: &[(char, usize)],
+    ) -> Self {
+        let columns = lines
+            .iter()
+            .map(|line| line.as_ref().chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut tilemap = Self::new(label, tileset, tile_size, columns, lines.len());
+        for (row, line) in lines.iter().enumerate() {
+            for (column, character) in line.as_ref().chars().enumerate() {
+                let tile = legend
+                    .iter()
+                    .find(|(legend_character, _)| *legend_character == character)
+                    .map(|(_, tile)| *tile);
+                tilemap.set_tile(column, row, tile);
+            }
+        }
+        tilemap
+    }
+
+    /// Load a tilemap from a RON file (see the [module-level docs](crate::tilemap)). Just like
+    /// the tileset image, `filepath` is relative to `assets/sprite`. If the file can't be read or
+    /// parsed, an error is printed and `None` is returned.
+    pub fn from_file<S: Into<String>, P: AsRef<Path>>(label: S, filepath: P) -> Option<Self> {
+        let filepath = PathBuf::from("assets/sprite").join(filepath);
+        let fh = match File::open(filepath) {
+            Ok(fh) => fh,
+            Err(e) => {
+                eprintln!("failed to open tilemap file: {}", e);
+                return None;
+            }
+        };
+        match ron::de::from_reader::<_, TilemapFile>(fh) {
+            Ok(file) => {
+                let legend: Vec<(char, usize)> = file.legend.into_iter().collect();
+                Some(Self::from_lines(
+                    label,
+                    file.tileset,
+                    file.tile_size,
+                    &file.grid,
+                    &legend,
+                ))
+            }
+            Err(e) => {
+                eprintln!("failed deserializing tilemap from file: {}", e);
+                None
+            }
+        }
+    }
+
+    /// How many cells across the tilemap is
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// How many cells down the tilemap is
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The size of the whole tilemap, in pixels
+    pub fn size(&self) -> Vec2 {
+        self.tile_size * Vec2::new(self.columns as f32, self.rows as f32)
+    }
+
+    /// The tile shown in a cell, or `None` if the cell is empty or outside of the tilemap
+    pub fn tile(&self, column: usize, row: usize) -> Option<usize> {
+        self.index(column, row).and_then(|idx| self.tiles[idx])
+    }
+
+    /// Show a tile in a cell, or empty the cell with `None`. Cells outside of the tilemap are
+    /// ignored.
+    pub fn set_tile(&mut self, column: usize, row: usize, tile: Option<usize>) {
+        if let Some(idx) = self.index(column, row) {
+            self.tiles[idx] = tile;
+        }
+    }
+
+    /// Whether a cell shows a solid tile
+    pub fn is_solid(&self, column: usize, row: usize) -> bool {
+        self.tile(column, row)
+            .map_or(false, |tile| self.tileset.solid.contains(&tile))
+    }
+
+    /// The cell (column and row) at a point in 2D game space, if the point is on the tilemap
+    pub fn cell_at(&self, point: Vec2) -> Option<(usize, usize)> {
+        let offset = (point - self.top_left()) / self.tile_size;
+        let (column, row) = (offset.x.floor(), (-offset.y).floor());
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        self.index(column, row).map(|_| (column, row))
+    }
+
+    /// Whether a point in 2D game space is on a cell that shows a solid tile
+    pub fn solid_at(&self, point: Vec2) -> bool {
+        self.cell_at(point)
+            .map_or(false, |(column, row)| self.is_solid(column, row))
+    }
+
+    /// The center of a cell in 2D game space
+    pub fn cell_center(&self, column: usize, row: usize) -> Vec2 {
+        self.top_left() + Vec2::new(column as f32 + 0.5, -(row as f32 + 0.5)) * self.tile_size
+    }
+
+    /// All of the cells (columns and rows) that show `tile`, row by row
+    pub fn cells_with(&self, tile: usize) -> Vec<(usize, usize)> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == Some(tile))
+            .map(|(idx, _)| (idx % self.columns, idx / self.columns))
+            .collect()
+    }
+
+    /// The corners of the solid cells that overlap a box in 2D game space
+    #[doc(hidden)]
+    pub fn solid_cells_in(&self, min: Vec2, max: Vec2) -> Vec<[Vec2; 4]> {
+        let top_left = self.top_left();
+        let first_column = ((min.x - top_left.x) / self.tile_size.x).floor().max(0.0) as usize;
+        let last_column = ((max.x - top_left.x) / self.tile_size.x).floor();
+        let first_row = ((top_left.y - max.y) / self.tile_size.y).floor().max(0.0) as usize;
+        let last_row = ((top_left.y - min.y) / self.tile_size.y).floor();
+        if last_column < 0.0 || last_row < 0.0 {
+            return Vec::new();
+        }
+        let last_column = (last_column as usize).min(self.columns.saturating_sub(1));
+        let last_row = (last_row as usize).min(self.rows.saturating_sub(1));
+        let half = self.tile_size / 2.0;
+        let mut cells = Vec::new();
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                if self.is_solid(column, row) {
+                    let center = self.cell_center(column, row);
+                    cells.push([
+                        center + Vec2::new(-half.x, half.y),
+                        center + half,
+                        center + Vec2::new(half.x, -half.y),
+                        center - half,
+                    ]);
+                }
+            }
+        }
+        cells
+    }
+
+    // The top-left corner of the tilemap in 2D game space
+    fn top_left(&self) -> Vec2 {
+        self.translation + Vec2::new(-self.size().x, self.size().y) / 2.0
+    }
+
+    // The index of a cell in `tiles`, if it's on the tilemap
+    fn index(&self, column: usize, row: usize) -> Option<usize> {
+        if column < self.columns && row < self.rows {
+            Some(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    // A mesh with a square for each cell that shows a tile, relative to the center of the tilemap
+    fn mesh(&self) -> Mesh {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        let half = self.tile_size / 2.0;
+        let tileset_size = Vec2::new(self.tileset.columns as f32, self.tileset.rows as f32);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let tile = match self.tile(column, row) {
+                    Some(tile) if tile < self.tileset.columns * self.tileset.rows => tile,
+                    _ => continue,
+                };
+                let center = self.cell_center(column, row) - self.translation;
+                // The tile's corners in the tileset image. UVs go down from the top of the image.
+                let uv_min = Vec2::new(
+                    (tile % self.tileset.columns) as f32,
+                    (tile / self.tileset.columns) as f32,
+                ) / tileset_size;
+                let uv_max = uv_min + Vec2::ONE / tileset_size;
+                let first = positions.len() as u32;
+                positions.extend_from_slice(&[
+                    [center.x - half.x, center.y - half.y, 0.0],
+                    [center.x + half.x, center.y - half.y, 0.0],
+                    [center.x + half.x, center.y + half.y, 0.0],
+                    [center.x - half.x, center.y + half.y, 0.0],
+                ]);
+                uvs.extend_from_slice(&[
+                    [uv_min.x, uv_max.y],
+                    [uv_max.x, uv_max.y],
+                    [uv_max.x, uv_min.y],
+                    [uv_min.x, uv_min.y],
+                ]);
+                indices.extend_from_slice(&[
+                    first,
+                    first + 1,
+                    first + 2,
+                    first,
+                    first + 2,
+                    first + 3,
+                ]);
+            }
+        }
+        if positions.is_empty() {
+            // Without any tiles, there's nothing to draw, but a mesh still needs some vertices
+            positions = vec![[0.0; 3]; 3];
+            uvs = vec![[0.0; 2]; 3];
+            indices = vec![0, 1, 2];
+        }
+        let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
+    fn bevy_transform(&self) -> Transform {
+        Transform::from_translation(self.translation.extend(self.layer))
+    }
+}
+
+// The Bevy entity that draws a tilemap, and the tilemap as it was when it was last drawn
+struct ShownTilemap {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+    tilemap: Tilemap,
+}
+
+// system - draw the tilemaps in `EngineState::tilemaps`, each as a single mesh. Meshes are only
+// rebuilt when their tilemaps change.
+fn sync_tilemaps(
+    mut commands: Commands,
+    engine_state: Res<EngineState>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shown_tilemaps: Local<HashMap<String, ShownTilemap>>,
+    mut query: Query<&mut Transform>,
+) {
+    // Remove the tilemaps that were removed
+    shown_tilemaps.retain(|label, shown| {
+        let keep = engine_state.tilemaps.contains_key(label);
+        if !keep {
+            commands.entity(shown.entity).despawn();
+        }
+        keep
+    });
+
+    for (label, tilemap) in engine_state.tilemaps.iter() {
+        let texture_path = PathBuf::from("sprite").join(&tilemap.tileset.filepath);
+        if !shown_tilemaps.contains_key(label) {
+            let mesh = meshes.add(tilemap.mesh());
+            let material = materials.add(ColorMaterial::texture(asset_server.load(texture_path)));
+            let entity = commands
+                .spawn()
+                .insert_bundle(SpriteBundle {
+                    // The mesh is already the right size
+                    sprite: Sprite {
+                        size: Vec2::ONE,
+                        resize_mode: SpriteResizeMode::Manual,
+                        ..Default::default()
+                    },
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: tilemap.bevy_transform(),
+                    ..Default::default()
+                })
+                .id();
+            shown_tilemaps.insert(
+                label.clone(),
+                ShownTilemap {
+                    entity,
+                    mesh,
+                    material,
+                    tilemap: tilemap.clone(),
+                },
+            );
+            continue;
+        }
+        // Unwrap: Can't crash because we just checked that it's there
+        let shown = shown_tilemaps.get_mut(label).unwrap();
+        if shown.tilemap == *tilemap {
+            continue;
+        }
+        if shown.tilemap.tiles != tilemap.tiles
+            || shown.tilemap.columns != tilemap.columns
+            || shown.tilemap.tile_size != tilemap.tile_size
+            || shown.tilemap.tileset.columns != tilemap.tileset.columns
+            || shown.tilemap.tileset.rows != tilemap.tileset.rows
+        {
+            if let Some(mesh) = meshes.get_mut(&shown.mesh) {
+                *mesh = tilemap.mesh();
+            }
+        }
+        if shown.tilemap.tileset.filepath != tilemap.tileset.filepath {
+            if let Some(material) = materials.get_mut(&shown.material) {
+                material.texture = Some(asset_server.load(texture_path));
+            }
+        }
+        if let Ok(mut transform) = query.get_mut(shown.entity) {
+            *transform = tilemap.bevy_transform();
+        }
+        shown.tilemap = tilemap.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three cells across and two down, 10 pixels each, centered on the origin. The top-left corner
+    // is at (-15, 10).
+    //
+    //     #.#
+    //     #
+    fn tilemap() -> Tilemap {
+        Tilemap::from_text(
+            "level",
+            Tileset::new("rolling/tiles.png", 2, 1).with_solid(&[1]),
+            Vec2::new(10.0, 10.0),
+            "\n#.#\n#\n",
+            &[('.', 0), ('#', 1)],
+        )
+    }
+
+    #[test]
+    fn from_text_reads_rows_of_characters() {
+        let tilemap = tilemap();
+        assert_eq!(tilemap.columns(), 3);
+        assert_eq!(tilemap.rows(), 2);
+        assert_eq!(tilemap.size(), Vec2::new(30.0, 20.0));
+        assert_eq!(tilemap.tile(0, 0), Some(1));
+        assert_eq!(tilemap.tile(1, 0), Some(0));
+        assert_eq!(tilemap.tile(2, 0), Some(1));
+        assert_eq!(tilemap.tile(0, 1), Some(1));
+        // Past the end of a short line, and outside of the tilemap
+        assert_eq!(tilemap.tile(1, 1), None);
+        assert_eq!(tilemap.tile(3, 0), None);
+        assert_eq!(tilemap.cells_with(1), vec![(0, 0), (2, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn from_text_ignores_characters_not_in_the_legend() {
+        let tilemap = Tilemap::from_text(
+            "level",
+            Tileset::new("rolling/tiles.png", 2, 1),
+            Vec2::new(10.0, 10.0),
+            "#x#",
+            &[('#', 1)],
+        );
+        assert_eq!(tilemap.columns(), 3);
+        assert_eq!(tilemap.tile(1, 0), None);
+    }
+
+    #[test]
+    fn from_file_loads_a_tilemap() {
+        let tilemap = Tilemap::from_file("labrinth", "rolling/labrinth.tilemap").unwrap();
+        assert_eq!(tilemap.tile_size, Vec2::new(48.0, 48.0));
+        assert!(tilemap.is_solid(0, 0));
+        assert!(!tilemap.is_solid(2, 1));
+        assert_eq!(tilemap.tile(1, 1), Some(3));
+        assert!(Tilemap::from_file("nope", "rolling/missing.tilemap").is_none());
+    }
+
+    #[test]
+    fn cell_at_finds_cells_up_to_the_edges() {
+        let tilemap = tilemap();
+        assert_eq!(tilemap.cell_at(Vec2::new(-15.0, 10.0)), Some((0, 0)));
+        assert_eq!(tilemap.cell_at(Vec2::new(-5.0, 0.0)), Some((1, 1)));
+        assert_eq!(tilemap.cell_at(Vec2::new(14.9, -9.9)), Some((2, 1)));
+        assert_eq!(tilemap.cell_at(Vec2::new(15.0, 0.0)), None);
+        assert_eq!(tilemap.cell_at(Vec2::new(0.0, -10.0)), None);
+        assert_eq!(tilemap.cell_at(Vec2::new(-15.1, 0.0)), None);
+        assert_eq!(tilemap.cell_at(Vec2::new(0.0, 10.1)), None);
+        assert!(tilemap.solid_at(Vec2::new(-10.0, -5.0)));
+        assert!(!tilemap.solid_at(Vec2::new(0.0, 5.0)));
+    }
+
+    #[test]
+    fn solid_cells_in_handles_boxes_past_the_edges() {
+        let tilemap = tilemap();
+        // A box around the whole tilemap finds every solid cell
+        assert_eq!(
+            tilemap
+                .solid_cells_in(Vec2::new(-100.0, -100.0), Vec2::new(100.0, 100.0))
+                .len(),
+            3
+        );
+        // A box hanging off the top-left corner only finds the corner cell
+        assert_eq!(
+            tilemap.solid_cells_in(Vec2::new(-20.0, 8.0), Vec2::new(-12.0, 20.0)),
+            vec![[
+                Vec2::new(-15.0, 10.0),
+                Vec2::new(-5.0, 10.0),
+                Vec2::new(-5.0, 0.0),
+                Vec2::new(-15.0, 0.0),
+            ]]
+        );
+        // A box hanging off the bottom-right corner finds nothing, since that cell is empty
+        assert!(tilemap
+            .solid_cells_in(Vec2::new(12.0, -20.0), Vec2::new(20.0, -8.0))
+            .is_empty());
+        // Boxes entirely off of the tilemap find nothing
+        assert!(tilemap
+            .solid_cells_in(Vec2::new(-50.0, -5.0), Vec2::new(-20.0, 5.0))
+            .is_empty());
+        assert!(tilemap
+            .solid_cells_in(Vec2::new(-5.0, 15.0), Vec2::new(5.0, 30.0))
+            .is_empty());
+        assert!(tilemap
+            .solid_cells_in(Vec2::new(20.0, -5.0), Vec2::new(50.0, 5.0))
+            .is_empty());
+    }
+}
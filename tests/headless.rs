@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use rusty_engine::prelude::*;
+
+const TIMESTEP: Duration = Duration::from_millis(10);
+
+#[derive(Default)]
+struct GameState {
+    frames: u32,
+    exit_after: Option<u32>,
+}
+
+rusty_engine::init!(GameState);
+
+fn logic(engine_state: &mut EngineState, game_state: &mut GameState) -> bool {
+    game_state.frames += 1;
+    let race_car = engine_state.sprites.get_mut("Race Car").unwrap();
+    race_car.translation.x += 100.0 * engine_state.delta_f32;
+    if Some(game_state.frames) == game_state.exit_after {
+        engine_state.should_exit = true;
+    }
+    true
+}
+
+fn new_game() -> Game {
+    let mut game = Game::new();
+    let race_car = game.add_sprite("Race Car", SpritePreset::RacingCarGreen);
+    race_car.translation = Vec2::new(-200.0, 0.0);
+    game.add_logic(logic);
+    game
+}
+
+#[test]
+fn runs_every_frame_with_a_fixed_timestep() {
+    let mut game = new_game();
+    let game_state = game.run_headless(GameState::default(), 50, TIMESTEP);
+
+    assert_eq!(game_state.frames, 50);
+    assert_eq!(game.time_since_startup, TIMESTEP * 50);
+    assert_eq!(game.delta, TIMESTEP);
+    // Half a second at 100 pixels per second
+    let race_car = game.sprites.get("Race Car").unwrap();
+    assert!((race_car.translation.x - -150.0).abs() < 0.01);
+}
+
+#[test]
+fn stops_early_when_the_game_exits() {
+    let mut game = new_game();
+    let game_state = game.run_headless(
+        GameState {
+            exit_after: Some(10),
+            ..Default::default()
+        },
+        50,
+        TIMESTEP,
+    );
+
+    assert_eq!(game_state.frames, 10);
+    assert_eq!(game.time_since_startup, TIMESTEP * 10);
+}